@@ -0,0 +1,161 @@
+use crate::board::{LockResult, TSpin};
+
+/// How a combo adds to the garbage a clear sends.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComboAttack {
+    /// Extra lines for each combo step, starting from the clear that begins the
+    /// combo. Steps past the end of the table send its last entry.
+    Table(Vec<u32>),
+    /// Tetr.io's scaling: each step multiplies the attack by this much more, and
+    /// a long enough combo of clears that would send nothing still sends some.
+    Multiplier(f32),
+}
+
+/// How many garbage lines each clear sends in versus play.
+///
+/// Line tables are indexed by the number of lines cleared, and clears larger
+/// than a table covers send its last entry. Locks that clear no lines never
+/// send anything.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackTable {
+    /// Lines sent by clears without a spin.
+    pub lines: Vec<u32>,
+    /// Lines sent by T-spin minis, and by all-spins when they are rewarded.
+    pub t_spin_mini: Vec<u32>,
+    /// Lines sent by full T-spins.
+    pub t_spin: Vec<u32>,
+    /// Extra lines sent by combos.
+    pub combo: ComboAttack,
+    /// Extra lines sent by a clear that continues a back-to-back chain.
+    pub back_to_back: u32,
+    /// Extra lines sent by a perfect clear.
+    pub perfect_clear: u32,
+}
+
+impl Default for AttackTable {
+    fn default() -> Self {
+        AttackTable::guideline()
+    }
+}
+
+fn lookup(table: &[u32], index: usize) -> u32 {
+    table
+        .get(index)
+        .or_else(|| table.last())
+        .copied()
+        .unwrap_or(0)
+}
+
+impl AttackTable {
+    /// The attack table of modern guideline games.
+    pub fn guideline() -> Self {
+        AttackTable {
+            lines: vec![0, 0, 1, 2, 4],
+            t_spin_mini: vec![0, 0, 1],
+            t_spin: vec![0, 2, 4, 6],
+            combo: ComboAttack::Table(vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5]),
+            back_to_back: 1,
+            perfect_clear: 10,
+        }
+    }
+    /// An approximation of Tetr.io's rules, whose combos multiply the attack
+    /// rather than adding to it.
+    pub fn tetrio() -> Self {
+        AttackTable {
+            combo: ComboAttack::Multiplier(0.25),
+            ..AttackTable::guideline()
+        }
+    }
+    /// Garbage lines sent by a lock.
+    pub fn attack(&self, result: LockResult) -> u32 {
+        let lines = result.lines_cleared;
+        if lines == 0 {
+            return 0;
+        }
+        let mut attack = match result.t_spin {
+            TSpin::Full => lookup(&self.t_spin, lines),
+            TSpin::Mini => lookup(&self.t_spin_mini, lines),
+            TSpin::None if result.all_spin => lookup(&self.t_spin_mini, lines),
+            TSpin::None => lookup(&self.lines, lines),
+        };
+        if result.back_to_back > 0 {
+            attack += self.back_to_back;
+        }
+        let step = result.combo.saturating_sub(1);
+        attack = match &self.combo {
+            ComboAttack::Table(table) => attack + lookup(table, step as usize),
+            ComboAttack::Multiplier(multiplier) if attack > 0 => {
+                (attack as f32 * (1.0 + multiplier * step as f32)) as u32
+            }
+            ComboAttack::Multiplier(_) => (1.0 + 1.25 * step as f32).ln() as u32,
+        };
+        if result.perfect_clear {
+            attack += self.perfect_clear;
+        }
+        attack
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guideline_line_clears() {
+        let table = AttackTable::guideline();
+        let sent: Vec<_> = (0..=4)
+            .map(|lines| table.attack(LockResult::clear(lines, TSpin::None)))
+            .collect();
+        assert_eq!(sent, [0, 0, 1, 2, 4]);
+        assert_eq!(table.attack(LockResult::clear(2, TSpin::Full)), 4);
+        assert_eq!(table.attack(LockResult::clear(1, TSpin::Mini)), 0);
+        assert_eq!(table.attack(LockResult::clear(0, TSpin::Full)), 0);
+    }
+
+    #[test]
+    fn all_spins_send_as_minis() {
+        let table = AttackTable::guideline();
+        let all_spin = LockResult {
+            all_spin: true,
+            ..LockResult::clear(2, TSpin::None)
+        };
+        assert_eq!(table.attack(all_spin), 1);
+    }
+
+    #[test]
+    fn guideline_bonuses_add_up() {
+        let table = AttackTable::guideline();
+        let chained = LockResult {
+            back_to_back: 2,
+            combo: 3,
+            ..LockResult::clear(4, TSpin::None)
+        };
+        assert_eq!(table.attack(chained), 4 + 1 + 1);
+        let long_combo = LockResult {
+            combo: 40,
+            ..LockResult::clear(1, TSpin::None)
+        };
+        assert_eq!(table.attack(long_combo), 5);
+        let pc = LockResult {
+            perfect_clear: true,
+            ..LockResult::clear(2, TSpin::Full)
+        };
+        assert_eq!(table.attack(pc), 14);
+    }
+
+    #[test]
+    fn tetrio_combos_multiply() {
+        let table = AttackTable::tetrio();
+        let chained = LockResult {
+            back_to_back: 2,
+            combo: 3,
+            ..LockResult::clear(4, TSpin::None)
+        };
+        assert_eq!(table.attack(chained), 7);
+        let singles = LockResult {
+            combo: 4,
+            ..LockResult::clear(1, TSpin::None)
+        };
+        assert_eq!(table.attack(singles), 1);
+    }
+}
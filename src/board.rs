@@ -0,0 +1,494 @@
+use crate::piece::{Kicks180, Piece, PieceKind};
+use crate::rotation::{RotationSystem, Srs};
+use std::sync::Arc;
+
+/// A single square of the playfield.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct Cell {
+    pub filled: Option<PieceKind>,
+}
+
+/// Whether a lock was a T-spin, and which kind.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TSpin {
+    #[default]
+    None,
+    /// Three corners around the T are blocked, but not both in front of it.
+    Mini,
+    /// Both corners in front of the T and at least one behind it are blocked,
+    /// or the rotation needed a one column, two row kick to fit.
+    Full,
+}
+
+/// Outcome of locking a piece into the board.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LockResult {
+    /// Number of lines cleared by the lock.
+    pub lines_cleared: usize,
+    /// Whether the lock was a T-spin.
+    pub t_spin: TSpin,
+    /// Whether the piece was rotated into a spot it could not move left, right
+    /// or up out of. Only reported when the ruleset rewards all-spins.
+    pub all_spin: bool,
+    /// Whether the piece locked entirely above the visible field, or partly
+    /// above the top of the board.
+    pub locked_out: bool,
+    /// Whether the lines cleared left the board completely empty.
+    pub perfect_clear: bool,
+    /// Number of line clearing locks in a row ending with this one, or 0 if
+    /// this lock cleared no lines.
+    pub combo: u32,
+    /// Number of difficult clears in an unbroken chain before this one, or 0
+    /// if this lock does not continue a back-to-back chain.
+    pub back_to_back: u32,
+}
+
+impl LockResult {
+    /// Whether the lock was a difficult clear, which builds back-to-back
+    /// chains: four or more lines at once, or any spin that cleared lines.
+    pub fn is_difficult(&self) -> bool {
+        self.lines_cleared >= 4
+            || (self.lines_cleared > 0 && (self.t_spin != TSpin::None || self.all_spin))
+    }
+}
+
+#[cfg(test)]
+impl LockResult {
+    /// The result of a first clear with no chain behind it.
+    pub(crate) fn clear(lines_cleared: usize, t_spin: TSpin) -> Self {
+        LockResult {
+            lines_cleared,
+            t_spin,
+            combo: 1,
+            ..LockResult::default()
+        }
+    }
+}
+
+/// The playfield: a field of visible rows topped by a hidden buffer zone.
+/// Row 0 is the bottom row and column 0 the leftmost column.
+#[derive(Debug)]
+pub struct Board {
+    board: Vec<Vec<Cell>>,
+    width: usize,
+    visible_height: usize,
+    rotation_system: Arc<dyn RotationSystem>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(10, 20, 20, Arc::new(Srs))
+    }
+}
+
+impl Board {
+    /// Creates an empty board `width` columns wide with `visible_height` visible
+    /// rows and `buffer_height` hidden rows above them, whose pieces turn
+    /// according to `rotation_system`.
+    ///
+    /// The buffer is made at least as tall as the tallest piece, so that pieces
+    /// always spawn inside the board where they can collide with the stack.
+    pub fn new(
+        width: usize,
+        visible_height: usize,
+        buffer_height: usize,
+        rotation_system: Arc<dyn RotationSystem>,
+    ) -> Self {
+        let tallest = (0..rotation_system.piece_count())
+            .flat_map(|index| (0..4).map(move |rotation| (PieceKind(index as u8), rotation)))
+            .filter_map(|(kind, rotation)| {
+                let top = rotation_system
+                    .cells(kind, rotation)
+                    .iter()
+                    .map(|&(row, _)| row)
+                    .max()?;
+                Some(top as i32 + 1 - rotation_system.bottom(kind, rotation))
+            })
+            .max()
+            .unwrap_or(0) as usize;
+        Board {
+            board: vec![vec![Cell::default(); width]; visible_height + buffer_height.max(tallest)],
+            width,
+            visible_height,
+            rotation_system,
+        }
+    }
+    /// Rows of the board from bottom to top, including the hidden buffer.
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.board
+    }
+    /// Number of columns.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    /// Number of rows shown to the player. Pieces locking entirely above this
+    /// height cause a lock out.
+    pub fn visible_height(&self) -> usize {
+        self.visible_height
+    }
+    /// Total number of rows, including the hidden buffer.
+    pub fn height(&self) -> usize {
+        self.board.len()
+    }
+    /// The rotation system that shapes and turns pieces on this board.
+    pub fn rotation_system(&self) -> &dyn RotationSystem {
+        &*self.rotation_system
+    }
+    /// A new piece of `kind` in its spawn rotation, centred horizontally with
+    /// its lowest cells just above the visible field.
+    pub fn spawn_piece(&self, kind: PieceKind) -> Piece {
+        let rotation = self.rotation_system.spawn_rotation(kind);
+        let cells = self.rotation_system.cells(kind, rotation);
+        let left = cells.iter().map(|&(_, column)| column as i32).min();
+        let right = cells.iter().map(|&(_, column)| column as i32).max();
+        let (left, right) = (left.unwrap_or(0), right.unwrap_or(0));
+        Piece {
+            kind,
+            column: (self.width as i32 - (right - left + 1)) / 2 - left,
+            row: self.visible_height as i32 - self.rotation_system.bottom(kind, rotation),
+            rotation,
+        }
+    }
+    /// Returns true if `piece` overlaps a filled cell, a wall or the floor.
+    /// Cells above the top of the board never collide.
+    pub fn check_collision(&self, piece: Piece) -> bool {
+        piece
+            .filled(self.rotation_system())
+            .any(|(x, y)| self.is_blocked(x, y))
+    }
+    fn is_blocked(&self, row: i32, column: i32) -> bool {
+        row < 0
+            || column < 0
+            || column as usize >= self.width
+            || self
+                .board
+                .get(row as usize)
+                .is_some_and(|cells| cells[column as usize].filled.is_some())
+    }
+    /// Returns true if `piece` cannot move one cell left, right or up.
+    pub fn is_immobile(&self, piece: Piece) -> bool {
+        [(0, -1), (0, 1), (1, 0)].iter().all(|&(row, column)| {
+            self.check_collision(Piece {
+                row: piece.row + row,
+                column: piece.column + column,
+                ..piece
+            })
+        })
+    }
+    /// Classifies `piece`, about to lock where it is after being rotated into
+    /// place with `kick`, using the three corner rule.
+    ///
+    /// Only the rotation system's T piece can spin. The walls and floor count as
+    /// blocked corners.
+    pub fn t_spin(&self, piece: Piece, kick: (i8, i8)) -> TSpin {
+        if self.rotation_system.t_piece() != Some(piece.kind) {
+            return TSpin::None;
+        }
+        let cells: Vec<(i32, i32)> = piece.filled(self.rotation_system()).collect();
+        let directions = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+        let center = cells.iter().copied().find(|&(row, column)| {
+            directions
+                .iter()
+                .filter(|&&(dr, dc)| cells.contains(&(row + dr, column + dc)))
+                .count()
+                == 3
+        });
+        let (row, column) = match center {
+            Some(center) => center,
+            None => return TSpin::None,
+        };
+        let back = directions
+            .iter()
+            .find(|&&(dr, dc)| !cells.contains(&(row + dr, column + dc)));
+        let (dr, dc) = match back {
+            Some(&(dr, dc)) => (-dr, -dc),
+            None => return TSpin::None,
+        };
+        let corners = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+        let blocked = corners
+            .iter()
+            .filter(|&&(cr, cc)| self.is_blocked(row + cr, column + cc))
+            .count();
+        let front_blocked = corners
+            .iter()
+            .filter(|&&(cr, cc)| cr == dr || cc == dc)
+            .all(|&(cr, cc)| self.is_blocked(row + cr, column + cc));
+        if blocked < 3 {
+            TSpin::None
+        } else if front_blocked || (kick.0.abs() == 1 && kick.1.abs() == 2) {
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
+    }
+    /// Shifts `piece` one column left, returning whether it moved.
+    pub fn move_piece_left(&self, piece: &mut Piece) -> bool {
+        piece.column -= 1;
+        if self.check_collision(*piece) {
+            piece.column += 1;
+            false
+        } else {
+            true
+        }
+    }
+    /// Shifts `piece` one column right, returning whether it moved.
+    pub fn move_piece_right(&self, piece: &mut Piece) -> bool {
+        piece.column += 1;
+        if self.check_collision(*piece) {
+            piece.column -= 1;
+            false
+        } else {
+            true
+        }
+    }
+    /// Shifts `piece` as far left as it will go.
+    pub fn das_left(&self, piece: &mut Piece) {
+        while self.move_piece_left(piece) {}
+    }
+    /// Shifts `piece` as far right as it will go.
+    pub fn das_right(&self, piece: &mut Piece) {
+        while self.move_piece_right(piece) {}
+    }
+    /// Moves `piece` down one row. If it cannot fall it is locked into the board
+    /// and the result of the lock is returned.
+    pub fn move_piece_down(&mut self, piece: &mut Piece) -> Option<LockResult> {
+        piece.row -= 1;
+        if self.check_collision(*piece) {
+            piece.row += 1;
+            Some(self.place_unchecked(*piece))
+        } else {
+            None
+        }
+    }
+    /// Rotates `piece` clockwise, trying each of the rotation system's wall
+    /// kicks in turn. Returns the kick that fit, or None and leaves the piece
+    /// unchanged if none did.
+    pub fn rotate_piece_clockwise(&self, piece: &mut Piece) -> Option<(i8, i8)> {
+        self.rotate_piece_quarter(piece, (piece.rotation + 1) % 4)
+    }
+    /// Rotates `piece` counterclockwise, trying each of the rotation system's
+    /// wall kicks in turn. Returns the kick that fit, or None and leaves the
+    /// piece unchanged if none did.
+    pub fn rotate_piece_counterclockwise(&self, piece: &mut Piece) -> Option<(i8, i8)> {
+        self.rotate_piece_quarter(piece, (piece.rotation + 3) % 4)
+    }
+    /// Rotates `piece` by 180 degrees, trying each kick from `kicks` in turn.
+    /// Returns the kick that fit, or None and leaves the piece unchanged if none
+    /// did.
+    pub fn rotate_piece_180(&self, piece: &mut Piece, kicks: Kicks180) -> Option<(i8, i8)> {
+        let kicks = kicks.kicks(self.rotation_system(), piece.kind, piece.rotation);
+        self.rotate_piece(piece, (piece.rotation + 2) % 4, kicks.iter().copied())
+    }
+    fn rotate_piece_quarter(&self, piece: &mut Piece, rotation: u8) -> Option<(i8, i8)> {
+        let kicks = self
+            .rotation_system
+            .kicks(piece.kind, piece.rotation, rotation);
+        self.rotate_piece(piece, rotation, kicks.iter().copied())
+    }
+    fn rotate_piece(
+        &self,
+        piece: &mut Piece,
+        rotation: u8,
+        kicks: impl Iterator<Item = (i8, i8)>,
+    ) -> Option<(i8, i8)> {
+        for (x, y) in kicks {
+            let kicked = Piece {
+                column: piece.column + x as i32,
+                row: piece.row + y as i32,
+                rotation,
+                ..*piece
+            };
+            if !self.check_collision(kicked) {
+                *piece = kicked;
+                return Some((x, y));
+            }
+        }
+        None
+    }
+    /// Moves `piece` straight down until it rests on the stack.
+    pub fn drop(&self, piece: &mut Piece) {
+        while self.try_move_piece_down(piece) {}
+    }
+    /// Moves `piece` down one row if there is room, returning whether it moved.
+    /// Unlike [`Board::move_piece_down`] this never locks the piece.
+    pub fn try_move_piece_down(&self, piece: &mut Piece) -> bool {
+        piece.row -= 1;
+        if self.check_collision(*piece) {
+            piece.row += 1;
+            false
+        } else {
+            true
+        }
+    }
+    /// Drops `piece` and locks it.
+    pub fn hard_drop(&mut self, mut piece: Piece) -> LockResult {
+        self.drop(&mut piece);
+        self.place_unchecked(piece)
+    }
+    /// Writes `piece` into the board without checking for collisions and clears
+    /// any completed lines.
+    pub fn place_unchecked(&mut self, piece: Piece) -> LockResult {
+        let result = self.place_without_clearing(piece);
+        self.clear_lines();
+        result
+    }
+    /// Writes `piece` into the board without checking for collisions, leaving
+    /// any lines it completes for [`Board::clear_lines`] to remove.
+    pub fn place_without_clearing(&mut self, piece: Piece) -> LockResult {
+        let locked_out = piece
+            .filled(self.rotation_system())
+            .all(|(x, _)| x as usize >= self.visible_height)
+            || piece
+                .filled(self.rotation_system())
+                .any(|(x, _)| x as usize >= self.height());
+        for (x, y) in piece.filled(&*self.rotation_system) {
+            if let Some(cell) = self
+                .board
+                .get_mut(x as usize)
+                .and_then(|row| row.get_mut(y as usize))
+            {
+                cell.filled = Some(piece.kind)
+            }
+        }
+        let lines_cleared = self.full_rows().count();
+        let perfect_clear = lines_cleared > 0
+            && self.board.iter().all(|row| {
+                row.iter().all(|cell| cell.filled.is_some())
+                    || row.iter().all(|cell| cell.filled.is_none())
+            });
+        LockResult {
+            lines_cleared,
+            locked_out,
+            perfect_clear,
+            ..LockResult::default()
+        }
+    }
+    /// Indices of the rows that are completely filled, from the bottom up.
+    pub fn full_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.board
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.iter().all(|cell| cell.filled.is_some()))
+            .map(|(index, _)| index)
+    }
+    /// Removes every completely filled row, moving the rows above down, and
+    /// returns how many were removed.
+    pub fn clear_lines(&mut self) -> usize {
+        let height = self.height();
+        self.board
+            .retain(|row| row.iter().any(|cell| cell.filled.is_none()));
+        let counter = height - self.board.len();
+        self.board.resize(height, vec![Cell::default(); self.width]);
+        counter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T_DOWN: Piece = Piece {
+        kind: PieceKind::T,
+        column: 3,
+        row: 0,
+        rotation: 2,
+    };
+
+    fn with_cells(cells: &[(usize, usize)]) -> Board {
+        let mut board = Board::default();
+        for &(row, column) in cells {
+            board.board[row][column].filled = Some(PieceKind::O);
+        }
+        board
+    }
+
+    #[test]
+    fn t_spin_with_both_front_corners_is_full() {
+        let board = with_cells(&[(0, 3), (0, 5), (2, 3)]);
+        assert_eq!(board.t_spin(T_DOWN, (0, 0)), TSpin::Full);
+    }
+
+    #[test]
+    fn t_spin_with_one_front_corner_is_mini() {
+        let board = with_cells(&[(0, 3), (2, 3), (2, 5)]);
+        assert_eq!(board.t_spin(T_DOWN, (0, 0)), TSpin::Mini);
+        assert_eq!(board.t_spin(T_DOWN, (1, 0)), TSpin::Mini);
+    }
+
+    #[test]
+    fn t_spin_needs_three_corners() {
+        let board = with_cells(&[(0, 3), (0, 5)]);
+        assert_eq!(board.t_spin(T_DOWN, (0, 0)), TSpin::None);
+    }
+
+    #[test]
+    fn t_spin_counts_the_floor_as_blocked() {
+        let board = with_cells(&[(1, 3)]);
+        let t_up = Piece {
+            row: -1,
+            rotation: 0,
+            ..T_DOWN
+        };
+        assert_eq!(board.t_spin(t_up, (0, 0)), TSpin::Mini);
+    }
+
+    #[test]
+    fn t_spin_triple_kick_upgrades_mini_to_full() {
+        let board = with_cells(&[(0, 3), (2, 3), (2, 5)]);
+        assert_eq!(board.t_spin(T_DOWN, (1, -2)), TSpin::Full);
+        assert_eq!(board.t_spin(T_DOWN, (-1, 2)), TSpin::Full);
+    }
+
+    #[test]
+    fn only_the_t_piece_spins() {
+        let board = with_cells(&[(0, 3), (0, 5), (2, 3), (2, 5)]);
+        let piece = Piece {
+            kind: PieceKind::S,
+            ..T_DOWN
+        };
+        assert_eq!(board.t_spin(piece, (0, 0)), TSpin::None);
+    }
+
+    #[test]
+    fn boxed_in_piece_is_immobile() {
+        let board = with_cells(&[(0, 3), (0, 5), (2, 4)]);
+        assert!(board.is_immobile(T_DOWN));
+        let board = with_cells(&[(0, 3), (0, 5)]);
+        assert!(!board.is_immobile(T_DOWN));
+        let board = with_cells(&[(2, 4)]);
+        assert!(!board.is_immobile(T_DOWN));
+    }
+
+    fn t_at_row(row: i32) -> Piece {
+        Piece {
+            row,
+            rotation: 0,
+            ..T_DOWN
+        }
+    }
+
+    #[test]
+    fn locking_entirely_above_the_visible_rows_locks_out() {
+        let mut board = Board::default();
+        assert!(board.place_without_clearing(t_at_row(20)).locked_out);
+        let mut board = Board::default();
+        assert!(!board.place_without_clearing(t_at_row(18)).locked_out);
+    }
+
+    #[test]
+    fn locking_partly_above_the_board_locks_out() {
+        let mut board = Board::new(10, 20, 0, Arc::new(Srs));
+        assert!(board.place_without_clearing(t_at_row(22)).locked_out);
+        assert!(board.rows()[23][4].filled.is_some());
+    }
+
+    #[test]
+    fn buffer_fits_the_tallest_piece() {
+        let board = Board::new(10, 20, 0, Arc::new(Srs));
+        assert_eq!(board.height(), 24);
+        let piece = board.spawn_piece(PieceKind::I);
+        assert!(piece
+            .filled(board.rotation_system())
+            .all(|(row, _)| (row as usize) < board.height()));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::attack::AttackTable;
+use crate::gravity::{GravityCurve, LevelProgression};
+use crate::piece::Kicks180;
+use crate::rotation::{RotationSystem, Srs};
+use std::sync::Arc;
+
+/// What resets the lock delay of a piece resting on the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockReset {
+    /// Only falling a row resets the delay.
+    Step,
+    /// Any successful move or rotation also resets the delay, up to
+    /// [`Config::max_lock_resets`] times. Falling below the piece's lowest row so
+    /// far restores the allowance.
+    Move,
+    /// Any successful move or rotation resets the delay, without limit.
+    Infinite,
+}
+
+/// How fast a piece falls while soft drop is held.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoftDrop {
+    /// A multiple of the current gravity.
+    Factor(f32),
+    /// A fixed number of rows per tick, or the current gravity if that is faster.
+    Rate(f32),
+    /// Straight to the stack, without locking the piece.
+    Infinite,
+}
+
+/// Which way the piece shifts while both left and right are held.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DasPriority {
+    /// The direction pressed most recently wins. Releasing it hands control
+    /// back to the other, which keeps the charge it built up.
+    #[default]
+    LastPressed,
+    /// Holding both directions stops the piece shifting either way.
+    Cancel,
+    /// The direction pressed first wins until it is released.
+    FirstPressed,
+}
+
+/// Rules that can vary from game to game.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Number of columns. Must be at least as wide as the widest piece.
+    pub width: usize,
+    /// Number of visible rows.
+    pub height: usize,
+    /// Hidden rows above the visible field. Pieces spawn here and may rotate
+    /// into it, but it is not drawn apart from a partial peek at its bottom row.
+    /// Raised to the height of the tallest piece if it is shorter.
+    pub buffer_height: usize,
+    /// How pieces are shaped, where they spawn and how they kick.
+    pub rotation_system: Arc<dyn RotationSystem>,
+    /// Kick table used by 180 degree rotations, by default the rotation
+    /// system's own.
+    pub kicks_180: Kicks180,
+    /// Whether any piece rotated into a spot it cannot move out of counts as a
+    /// spin, not just T-spins.
+    pub all_spins: bool,
+    /// Delayed auto shift: ticks a direction must be held before the piece
+    /// starts shifting on its own.
+    pub das: u32,
+    /// Auto repeat rate: ticks between moves once auto shift has started. At 0
+    /// the piece shifts straight to the wall.
+    pub arr: u32,
+    /// Ticks auto shift is held off after a rotation or a new piece spawning, so
+    /// a charged shift does not carry a piece past where it was aimed.
+    pub das_cut: u32,
+    /// Which direction wins while both are held.
+    pub das_priority: DasPriority,
+    /// Gravity at each level.
+    pub gravity: GravityCurve,
+    /// Level the game starts on.
+    pub start_level: u32,
+    /// How the level rises.
+    pub level_progression: LevelProgression,
+    /// Initial rotation: a rotation held as a piece spawns turns it before it
+    /// enters, if it fits.
+    pub initial_rotation: bool,
+    /// Initial hold: a hold held as a piece spawns swaps it with the held piece
+    /// before it enters.
+    pub initial_hold: bool,
+    /// Soft drop speed.
+    pub soft_drop: SoftDrop,
+    /// Entry delay (ARE): ticks between a piece locking and the next one
+    /// spawning.
+    pub entry_delay: u32,
+    /// Ticks completed lines stay on the board before they are removed. Runs
+    /// before the entry delay.
+    pub line_clear_delay: u32,
+    /// Ticks a piece may rest on the stack before it locks.
+    pub lock_delay: u32,
+    /// What resets the lock delay.
+    pub lock_reset: LockReset,
+    /// Number of move resets allowed under [`LockReset::Move`]. A piece that has
+    /// used them all locks as soon as it touches the stack.
+    pub max_lock_resets: u32,
+    /// Garbage lines each clear sends.
+    pub attack: AttackTable,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            width: 10,
+            height: 20,
+            buffer_height: 20,
+            rotation_system: Arc::new(Srs),
+            kicks_180: Kicks180::System,
+            all_spins: false,
+            das: 3,
+            arr: 0,
+            das_cut: 0,
+            das_priority: DasPriority::LastPressed,
+            gravity: GravityCurve::Guideline,
+            start_level: 1,
+            level_progression: LevelProgression::Lines(10),
+            initial_rotation: false,
+            initial_hold: false,
+            soft_drop: SoftDrop::Rate(1.0),
+            entry_delay: 0,
+            line_clear_delay: 0,
+            lock_delay: 30,
+            lock_reset: LockReset::Move,
+            max_lock_resets: 15,
+            attack: AttackTable::guideline(),
+        }
+    }
+}
@@ -1,4 +1,156 @@
 pub static FILLED: [[(u8, u8); 28]; 4] = [
+    [
+        (0, 0), //O1
+        (0, 1),
+        (1, 0),
+        (1, 1),
+        (1, 0), //T1
+        (1, 1),
+        (1, 2),
+        (2, 1),
+        (1, 0), //L1
+        (1, 1),
+        (1, 2),
+        (2, 2),
+        (1, 0), //J1
+        (1, 1),
+        (1, 2),
+        (2, 0),
+        (1, 0), //S1
+        (1, 1),
+        (2, 1),
+        (2, 2),
+        (1, 1), //Z1
+        (1, 2),
+        (2, 0),
+        (2, 1),
+        (2, 0), //I1
+        (2, 1),
+        (2, 2),
+        (2, 3),
+    ],
+    [
+        (0, 0), //O2
+        (0, 1),
+        (1, 0),
+        (1, 1),
+        (0, 1), //T2
+        (1, 1),
+        (1, 2),
+        (2, 1),
+        (0, 1), //L2
+        (0, 2),
+        (1, 1),
+        (2, 1),
+        (0, 1), //J2
+        (1, 1),
+        (2, 1),
+        (2, 2),
+        (0, 2), //S2
+        (1, 1),
+        (1, 2),
+        (2, 1),
+        (0, 1), //Z2
+        (1, 1),
+        (1, 2),
+        (2, 2),
+        (0, 2), //I2
+        (1, 2),
+        (2, 2),
+        (3, 2),
+    ],
+    [
+        (0, 0), //O3
+        (0, 1),
+        (1, 0),
+        (1, 1),
+        (0, 1), //T3
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (0, 0), //L3
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (0, 2), //J3
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (0, 0), //S3
+        (0, 1),
+        (1, 1),
+        (1, 2),
+        (0, 1), //Z3
+        (0, 2),
+        (1, 0),
+        (1, 1),
+        (1, 0), //I3
+        (1, 1),
+        (1, 2),
+        (1, 3),
+    ],
+    [
+        (0, 0), //O4
+        (0, 1),
+        (1, 0),
+        (1, 1),
+        (0, 1), //T4
+        (1, 0),
+        (1, 1),
+        (2, 1),
+        (0, 1), //L4
+        (1, 1),
+        (2, 0),
+        (2, 1),
+        (0, 0), //J4
+        (0, 1),
+        (1, 1),
+        (2, 1),
+        (0, 1), //S4
+        (1, 0),
+        (1, 1),
+        (2, 0),
+        (0, 0), //Z4
+        (1, 0),
+        (1, 1),
+        (2, 1),
+        (0, 1), //I4
+        (1, 1),
+        (2, 1),
+        (3, 1),
+    ],
+];
+
+pub static KICKS: [[(i8, i8); 5]; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], //0->R
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    //0->L
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     //R->2
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     //R->0
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    //2->L
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], //2->R
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  //L->0
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  //L->2
+];
+
+pub static I_KICKS: [[(i8, i8); 5]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], //0->R
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], //0->L
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], //R->2
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], //R->0
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], //2->L
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], //2->R
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], //L->0
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], //L->2
+];
+
+pub static SRS_PLUS_180_KICKS: [[(i8, i8); 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)], //0->2
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],   //R->L
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)], //2->0
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)], //L->R
+];
+
+pub static ARS_FILLED: [[(u8, u8); 28]; 4] = [
     [
         (0, 0), //O1
         (0, 1),
@@ -24,40 +176,163 @@ pub static FILLED: [[(u8, u8); 28]; 4] = [
         (0, 2),
         (1, 0),
         (1, 1),
-        (0, 0), //I1
-        (0, 1),
-        (0, 2),
-        (0, 3),
+        (2, 0), //I1
+        (2, 1),
+        (2, 2),
+        (2, 3),
     ],
     [
         (0, 0), //O2
         (0, 1),
         (1, 0),
         (1, 1),
-        (0, 0), //T2
+        (0, 1), //T2
+        (1, 1),
+        (1, 2),
+        (2, 1),
+        (0, 1), //L2
+        (0, 2),
+        (1, 1),
+        (2, 1),
+        (0, 1), //J2
+        (1, 1),
+        (2, 1),
+        (2, 2),
+        (0, 1), //S2
         (1, 0),
         (1, 1),
         (2, 0),
-        (0, 0), //L2
+        (0, 1), //Z2
+        (1, 1),
+        (1, 2),
+        (2, 2),
+        (0, 2), //I2
+        (1, 2),
+        (2, 2),
+        (3, 2),
+    ],
+    [
+        (0, 0), //O3
         (0, 1),
         (1, 0),
-        (2, 0),
-        (0, 0), //J2
+        (1, 1),
+        (0, 1), //T3
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (0, 0), //L3
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (0, 2), //J3
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (0, 0), //S3
+        (0, 1),
+        (1, 1),
+        (1, 2),
+        (0, 1), //Z3
+        (0, 2),
         (1, 0),
+        (1, 1),
+        (2, 0), //I3
+        (2, 1),
+        (2, 2),
+        (2, 3),
+    ],
+    [
+        (0, 0), //O4
+        (0, 1),
+        (1, 0),
+        (1, 1),
+        (0, 1), //T4
+        (1, 0),
+        (1, 1),
+        (2, 1),
+        (0, 1), //L4
+        (1, 1),
         (2, 0),
         (2, 1),
-        (0, 1), //S2
+        (0, 0), //J4
+        (0, 1),
+        (1, 1),
+        (2, 1),
+        (0, 1), //S4
         (1, 0),
         (1, 1),
         (2, 0),
-        (0, 0), //Z2
+        (0, 1), //Z4
+        (1, 1),
+        (1, 2),
+        (2, 2),
+        (0, 2), //I4
+        (1, 2),
+        (2, 2),
+        (3, 2),
+    ],
+];
+
+pub static NRS_FILLED: [[(u8, u8); 28]; 4] = [
+    [
+        (0, 0), //O1
+        (0, 1),
         (1, 0),
         (1, 1),
+        (1, 0), //T1
+        (1, 1),
+        (1, 2),
         (2, 1),
-        (0, 0), //I2
-        (1, 0),
+        (1, 0), //L1
+        (1, 1),
+        (1, 2),
+        (2, 2),
+        (1, 0), //J1
+        (1, 1),
+        (1, 2),
         (2, 0),
-        (3, 0),
+        (0, 0), //S1
+        (0, 1),
+        (1, 1),
+        (1, 2),
+        (0, 1), //Z1
+        (0, 2),
+        (1, 0),
+        (1, 1),
+        (1, 0), //I1
+        (1, 1),
+        (1, 2),
+        (1, 3),
+    ],
+    [
+        (0, 0), //O2
+        (0, 1),
+        (1, 0),
+        (1, 1),
+        (0, 1), //T2
+        (1, 1),
+        (1, 2),
+        (2, 1),
+        (0, 1), //L2
+        (0, 2),
+        (1, 1),
+        (2, 1),
+        (0, 1), //J2
+        (1, 1),
+        (2, 1),
+        (2, 2),
+        (0, 2), //S2
+        (1, 1),
+        (1, 2),
+        (2, 1),
+        (0, 1), //Z2
+        (1, 1),
+        (1, 2),
+        (2, 2),
+        (0, 2), //I2
+        (1, 2),
+        (2, 2),
+        (3, 2),
     ],
     [
         (0, 0), //O3
@@ -84,10 +359,10 @@ pub static FILLED: [[(u8, u8); 28]; 4] = [
         (0, 2),
         (1, 0),
         (1, 1),
-        (0, 0), //I3
-        (0, 1),
-        (0, 2),
-        (0, 3),
+        (1, 0), //I3
+        (1, 1),
+        (1, 2),
+        (1, 3),
     ],
     [
         (0, 0), //O4
@@ -106,27 +381,17 @@ pub static FILLED: [[(u8, u8); 28]; 4] = [
         (0, 1),
         (1, 1),
         (2, 1),
-        (0, 1), //S4
-        (1, 0),
-        (1, 1),
-        (2, 0),
-        (0, 0), //Z4
-        (1, 0),
+        (0, 2), //S4
         (1, 1),
+        (1, 2),
         (2, 1),
-        (0, 0), //I4
-        (1, 0),
-        (2, 0),
-        (3, 0),
+        (0, 1), //Z4
+        (1, 1),
+        (1, 2),
+        (2, 2),
+        (0, 2), //I4
+        (1, 2),
+        (2, 2),
+        (3, 2),
     ],
 ];
-
-pub static ROTATION_OFFSETS: [[(u8, u8); 4]; 7] = [
-    [(0, 0), (0, 0), (0, 0), (0, 0)], //O
-    [(0, 1), (1, 0), (0, 0), (0, 0)],
-    [(0, 1), (1, 0), (0, 0), (0, 0)],
-    [(0, 1), (1, 0), (0, 0), (0, 0)],
-    [(0, 1), (1, 0), (0, 0), (0, 0)],
-    [(0, 1), (1, 0), (0, 0), (0, 0)],
-    [(0, 2), (2, 0), (0, 1), (1, 0)],
-];
@@ -0,0 +1,1289 @@
+use crate::board::{Board, LockResult, TSpin};
+use crate::config::{Config, DasPriority, LockReset, SoftDrop};
+use crate::gravity::{LevelProgression, TWENTY_G};
+use crate::input::{GameAction, InputEvent};
+use crate::piece::{Piece, PieceKind};
+use crate::score::{self, HARD_DROP_POINTS, SOFT_DROP_POINTS};
+use crate::state::{GameResults, GameState, TopOut};
+use rand::prelude::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use std::collections::HashSet;
+use std::mem::{swap, take};
+use std::time::Duration;
+
+/// Number of times per second [`Tetris::update`] is expected to be called.
+///
+/// All game timing is measured in these ticks rather than wall-clock time, so
+/// replaying the same inputs on the same ticks always produces the same game.
+pub const TICKS_PER_SECOND: u32 = 60;
+
+const READY_TICKS: u32 = 3 * TICKS_PER_SECOND;
+const RESULTS_DELAY_TICKS: u32 = TICKS_PER_SECOND;
+
+fn generate_batch(rng: &mut Pcg32, batch: &mut Vec<PieceKind>, piece_count: usize) {
+    *batch = (0..piece_count)
+        .map(|index| PieceKind(index as u8))
+        .collect();
+    batch.shuffle(rng);
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Shift {
+    order: u64,
+    das_time: u64,
+}
+
+/// A single game of 40 line sprint.
+///
+/// The game is advanced one tick at a time by calling [`Tetris::update`]
+/// [`TICKS_PER_SECOND`] times per second, and is driven by feeding it
+/// [`InputEvent`]s through [`Tetris::handle_input`]. What each tick and input
+/// does depends on the current [`GameState`].
+///
+/// Pieces are dealt from a bag shuffled by a seeded PCG generator, so two games
+/// started with the same seed receive the same piece sequence.
+#[derive(Debug)]
+pub struct Tetris {
+    config: Config,
+    frame: u64,
+    gravity: f32,
+    level: u32,
+    lines_cleared: u32,
+    score: u64,
+    combo: u32,
+    back_to_back: u32,
+    perfect_clears: u32,
+    attack: u32,
+    fall_progress: f32,
+    board: Board,
+    seed: u64,
+    random_seed: bool,
+    rng: Pcg32,
+    current_batch: Vec<PieceKind>,
+    next_batch: Vec<PieceKind>,
+    current_piece: Option<Piece>,
+    shift_left: Option<Shift>,
+    shift_right: Option<Shift>,
+    shift_presses: u64,
+    last_kick: Option<(i8, i8)>,
+    lock_timer: u32,
+    lock_resets: u32,
+    lowest_row: i32,
+    last_lock: Option<LockResult>,
+    hold: (bool, Option<PieceKind>),
+    soft_dropping: bool,
+    held: HashSet<GameAction>,
+    buffered: Vec<GameAction>,
+    entry_timer: u32,
+    clear_timer: u32,
+    lines_remaining: Option<isize>,
+    state: GameState,
+    top_out: Option<TopOut>,
+}
+
+impl Default for Tetris {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tetris {
+    /// Starts a new 40 line sprint with a random seed. Each restart draws a new
+    /// seed.
+    pub fn new() -> Self {
+        Self::with_random_seed(Config::default())
+    }
+    /// Starts a new 40 line sprint with the given rules and a random seed. Each
+    /// restart draws a new seed.
+    pub fn with_random_seed(config: Config) -> Self {
+        Tetris {
+            random_seed: true,
+            ..Self::with_config(rand::random(), config)
+        }
+    }
+    /// Starts a new 40 line sprint whose piece sequence is determined by `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_config(seed, Config::default())
+    }
+    /// Starts a new 40 line sprint with the given seed and rules. Restarting
+    /// replays the same piece sequence.
+    pub fn with_config(seed: u64, config: Config) -> Self {
+        Tetris {
+            board: Board::new(
+                config.width,
+                config.height,
+                config.buffer_height,
+                config.rotation_system.clone(),
+            ),
+            gravity: config.gravity.gravity(config.start_level),
+            level: config.start_level,
+            config,
+            frame: 0,
+            lines_cleared: 0,
+            score: 0,
+            combo: 0,
+            back_to_back: 0,
+            perfect_clears: 0,
+            attack: 0,
+            fall_progress: 0.0,
+            seed,
+            random_seed: false,
+            rng: Pcg32::seed_from_u64(seed),
+            current_batch: Vec::new(),
+            next_batch: Vec::new(),
+            current_piece: None,
+            shift_left: None,
+            shift_right: None,
+            shift_presses: 0,
+            last_kick: None,
+            lock_timer: 0,
+            lock_resets: 0,
+            lowest_row: 0,
+            last_lock: None,
+            hold: (false, None),
+            soft_dropping: false,
+            held: HashSet::new(),
+            buffered: Vec::new(),
+            entry_timer: 0,
+            clear_timer: 0,
+            lines_remaining: Some(40),
+            state: GameState::Title,
+            top_out: None,
+        }
+    }
+    /// The rules this game is played with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+    /// The seed used to generate this game's piece sequence.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// The playfield.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+    /// The piece currently under the player's control, if any.
+    pub fn current_piece(&self) -> Option<Piece> {
+        self.current_piece
+    }
+    /// Where the current piece would land if hard dropped.
+    pub fn ghost_piece(&self) -> Option<Piece> {
+        let mut ghost = self.current_piece?;
+        self.board.drop(&mut ghost);
+        Some(ghost)
+    }
+    /// The held piece, if any.
+    pub fn hold_piece(&self) -> Option<PieceKind> {
+        self.hold.1
+    }
+    /// Upcoming pieces in the order they will be dealt.
+    pub fn queue(&self) -> impl Iterator<Item = PieceKind> + '_ {
+        self.current_batch
+            .iter()
+            .rev()
+            .chain(self.next_batch.iter().rev())
+            .copied()
+    }
+    /// How far the current piece is through its lock delay, from 0 when it is
+    /// falling or has just been reset to 1 when it is about to lock.
+    pub fn lock_progress(&self) -> f32 {
+        if self.config.lock_delay == 0 {
+            0.0
+        } else {
+            (self.lock_timer as f32 / self.config.lock_delay as f32).min(1.0)
+        }
+    }
+    /// Whether completed lines are being shown before they are removed.
+    pub fn is_clearing_lines(&self) -> bool {
+        self.clear_timer > 0
+    }
+    /// The result of the most recent lock, if a piece has locked yet.
+    pub fn last_lock(&self) -> Option<LockResult> {
+        self.last_lock
+    }
+    /// How the game was lost, if it has been.
+    pub fn top_out(&self) -> Option<TopOut> {
+        self.top_out
+    }
+    /// The current level, which sets the gravity.
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+    /// Total lines cleared so far.
+    pub fn lines_cleared(&self) -> u32 {
+        self.lines_cleared
+    }
+    /// Points scored so far.
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+    /// Number of line clearing locks in a row so far, or 0 if the last lock
+    /// cleared no lines.
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+    /// Number of difficult clears in the current back-to-back chain. Locks that
+    /// clear no lines leave the chain intact.
+    pub fn back_to_back(&self) -> u32 {
+        self.back_to_back
+    }
+    /// Number of perfect clears made so far.
+    pub fn perfect_clears(&self) -> u32 {
+        self.perfect_clears
+    }
+    /// Total garbage lines sent so far, according to the configured attack
+    /// table.
+    pub fn attack(&self) -> u32 {
+        self.attack
+    }
+    /// Current gravity in rows per tick.
+    pub fn gravity(&self) -> f32 {
+        self.gravity
+    }
+    /// Lines left to clear before the sprint is complete.
+    pub fn lines_remaining(&self) -> Option<isize> {
+        self.lines_remaining
+    }
+    /// The current phase of the game.
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+    /// Number of ticks simulated so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+    /// In-game time spent in the current run, or the final time once it is finished.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.frame * 1000 / TICKS_PER_SECOND as u64)
+    }
+    /// The final statistics of the run, once it is over.
+    pub fn results(&self) -> Option<GameResults> {
+        if !self.state.is_over() {
+            return None;
+        }
+        Some(GameResults {
+            time: self.elapsed(),
+            lines_cleared: self.lines_cleared,
+            level: self.level,
+            score: self.score,
+            perfect_clears: self.perfect_clears,
+            attack: self.attack,
+            top_out: self.top_out,
+        })
+    }
+    fn next_piece(&mut self) -> PieceKind {
+        if self.current_batch.is_empty() {
+            let piece_count = self.board.rotation_system().piece_count();
+            swap(&mut self.current_batch, &mut self.next_batch);
+            generate_batch(&mut self.rng, &mut self.next_batch, piece_count);
+            if self.current_batch.is_empty() {
+                generate_batch(&mut self.rng, &mut self.current_batch, piece_count)
+            }
+        }
+        self.current_batch.pop().unwrap()
+    }
+    fn switch_hold(&mut self) {
+        if !self.hold.0 {
+            if let Some(current) = self.current_piece {
+                let kind = match self.hold.1.replace(current.kind) {
+                    Some(kind) => kind,
+                    None => self.next_piece(),
+                };
+                self.hold.0 = true;
+                self.spawn_piece(kind);
+            }
+        }
+    }
+    fn spawn_piece(&mut self, kind: PieceKind) {
+        let mut piece = self.board.spawn_piece(kind);
+        if self.config.initial_rotation {
+            let turns = [
+                (GameAction::Rotate180, 2),
+                (GameAction::RotateCW, 1),
+                (GameAction::RotateCCW, 3),
+            ];
+            if let Some(&(_, turn)) = turns.iter().find(|(action, _)| self.held.contains(action)) {
+                let rotated = Piece {
+                    rotation: (piece.rotation + turn) % 4,
+                    ..piece
+                };
+                if !self.board.check_collision(rotated) {
+                    piece = rotated;
+                }
+            }
+        }
+        if self.board.check_collision(piece) {
+            self.current_piece = Some(piece);
+            self.end_game(TopOut::BlockOut);
+            return;
+        }
+        self.board.try_move_piece_down(&mut piece);
+        self.last_kick = None;
+        self.lock_timer = 0;
+        self.lock_resets = 0;
+        self.lowest_row = piece.row;
+        self.cut_das();
+        self.fall_instantly(&mut piece);
+        self.current_piece = Some(piece);
+    }
+    fn falls_instantly(&self) -> bool {
+        self.gravity >= TWENTY_G
+            || (self.soft_dropping && self.config.soft_drop == SoftDrop::Infinite)
+    }
+    fn fall_instantly(&mut self, piece: &mut Piece) {
+        if !self.falls_instantly() {
+            return;
+        }
+        while self.board.try_move_piece_down(piece) {
+            self.stepped_down(piece.row);
+            if self.soft_dropping {
+                self.score += SOFT_DROP_POINTS;
+            }
+        }
+    }
+    fn cut_das(&mut self) {
+        let cut = self.frame + self.config.das_cut as u64;
+        for shift in self
+            .shift_left
+            .iter_mut()
+            .chain(self.shift_right.iter_mut())
+        {
+            shift.das_time = shift.das_time.max(cut);
+        }
+    }
+    fn moved(&mut self) {
+        self.last_kick = None;
+        self.reset_lock_delay();
+    }
+    fn rotated(&mut self, kick: (i8, i8)) {
+        self.last_kick = Some(kick);
+        self.cut_das();
+        self.reset_lock_delay();
+    }
+    fn is_grounded(&self, mut piece: Piece) -> bool {
+        !self.board.try_move_piece_down(&mut piece)
+    }
+    fn reset_lock_delay(&mut self) {
+        // Called before the moved piece is stored, so this asks whether the
+        // piece was resting on the stack when the move was made.
+        if !self
+            .current_piece
+            .is_some_and(|piece| self.is_grounded(piece))
+        {
+            return;
+        }
+        match self.config.lock_reset {
+            LockReset::Step => {}
+            LockReset::Move => {
+                if self.lock_resets < self.config.max_lock_resets {
+                    self.lock_timer = 0;
+                    self.lock_resets += 1;
+                }
+            }
+            LockReset::Infinite => self.lock_timer = 0,
+        }
+    }
+    fn stepped_down(&mut self, row: i32) {
+        self.last_kick = None;
+        self.lock_timer = 0;
+        if row < self.lowest_row {
+            self.lowest_row = row;
+            self.lock_resets = 0;
+        }
+    }
+    fn lock_piece(&mut self, piece: Piece) {
+        let t_spin = match self.last_kick {
+            Some(kick) => self.board.t_spin(piece, kick),
+            None => TSpin::None,
+        };
+        let all_spin =
+            self.config.all_spins && self.last_kick.is_some() && self.board.is_immobile(piece);
+        let mut result = LockResult {
+            t_spin,
+            all_spin,
+            ..self.board.place_without_clearing(piece)
+        };
+        if result.lines_cleared == 0 {
+            self.combo = 0;
+        } else {
+            self.combo += 1;
+            result.combo = self.combo;
+            if result.is_difficult() {
+                result.back_to_back = self.back_to_back;
+                self.back_to_back += 1;
+            } else {
+                self.back_to_back = 0;
+            }
+        }
+        if result.lines_cleared > 0 {
+            if self.config.line_clear_delay == 0 {
+                self.board.clear_lines();
+            } else {
+                self.clear_timer = self.config.line_clear_delay;
+            }
+        }
+        self.entry_timer = self.config.entry_delay;
+        self.last_lock = Some(result);
+        self.score += score::level_points(result, self.level);
+        if let Some(remaining) = &mut self.lines_remaining {
+            *remaining -= result.lines_cleared as isize;
+        }
+        self.lines_cleared += result.lines_cleared as u32;
+        self.perfect_clears += result.perfect_clear as u32;
+        self.attack += self.config.attack.attack(result);
+        match self.config.level_progression {
+            LevelProgression::Lines(lines) => {
+                self.set_level(self.config.start_level + self.lines_cleared / lines.max(1))
+            }
+            LevelProgression::Tgm => {
+                self.set_level((self.level + result.lines_cleared as u32).min(999))
+            }
+        }
+        self.current_piece = None;
+        self.hold.0 = false;
+        if result.locked_out {
+            self.end_game(TopOut::LockOut);
+        }
+    }
+    fn set_level(&mut self, level: u32) {
+        self.level = level;
+        self.gravity = self.config.gravity.gravity(level);
+    }
+    fn end_game(&mut self, top_out: TopOut) {
+        self.top_out = Some(top_out);
+        self.state = GameState::ToppedOut(RESULTS_DELAY_TICKS);
+    }
+    fn restart(&mut self) {
+        let held = take(&mut self.held);
+        *self = if self.random_seed {
+            Tetris::with_random_seed(self.config.clone())
+        } else {
+            Tetris::with_config(self.seed, self.config.clone())
+        };
+        if held.contains(&GameAction::MoveLeft) {
+            self.shift_left = self.press_shift();
+        }
+        if held.contains(&GameAction::MoveRight) {
+            self.shift_right = self.press_shift();
+        }
+        self.held = held;
+        self.state = GameState::Ready(READY_TICKS);
+    }
+    /// Advances the game by one tick.
+    pub fn update(&mut self) {
+        self.state = match self.state {
+            GameState::Ready(0) => GameState::Playing,
+            GameState::Ready(ticks) => GameState::Ready(ticks - 1),
+            GameState::Playing => {
+                self.update_playing();
+                return;
+            }
+            GameState::Finished(0) | GameState::ToppedOut(0) => GameState::Results,
+            GameState::Finished(ticks) => GameState::Finished(ticks - 1),
+            GameState::ToppedOut(ticks) => GameState::ToppedOut(ticks - 1),
+            state => state,
+        };
+    }
+    fn update_playing(&mut self) {
+        if let Some(lines) = self.lines_remaining {
+            if lines <= 0 {
+                self.state = GameState::Finished(RESULTS_DELAY_TICKS);
+                return;
+            }
+        }
+        self.frame += 1;
+        if self.clear_timer > 0 {
+            self.clear_timer -= 1;
+            if self.clear_timer == 0 {
+                self.board.clear_lines();
+            }
+            return;
+        }
+        if self.entry_timer > 0 {
+            self.entry_timer -= 1;
+            return;
+        }
+        if self.current_piece.is_none() {
+            let mut kind = self.next_piece();
+            if self.config.initial_hold && self.held.contains(&GameAction::Hold) {
+                kind = match self.hold.1.replace(kind) {
+                    Some(kind) => kind,
+                    None => self.next_piece(),
+                };
+                self.hold.0 = true;
+            }
+            if self.config.level_progression == LevelProgression::Tgm
+                && self.level % 100 != 99
+                && self.level < 998
+            {
+                self.set_level(self.level + 1);
+            }
+            self.spawn_piece(kind);
+            if self.state != GameState::Playing {
+                return;
+            }
+            self.replay_buffered();
+            if self.current_piece.is_none() {
+                return;
+            }
+        }
+        if let Some(mut piece) = self.current_piece {
+            if let Some((Shift { das_time, .. }, right)) = self.shift_direction() {
+                if das_time <= self.frame {
+                    let steps = if self.config.arr == 0 {
+                        self.board.width()
+                    } else if (self.frame - das_time).is_multiple_of(self.config.arr as u64) {
+                        1
+                    } else {
+                        0
+                    };
+                    let column = piece.column;
+                    for _ in 0..steps {
+                        let moved = if right {
+                            self.board.move_piece_right(&mut piece)
+                        } else {
+                            self.board.move_piece_left(&mut piece)
+                        };
+                        if !moved {
+                            break;
+                        }
+                        self.fall_instantly(&mut piece);
+                    }
+                    if piece.column != column {
+                        self.moved();
+                    }
+                }
+            }
+            self.fall_progress += match (self.soft_dropping, self.config.soft_drop) {
+                _ if self.gravity >= TWENTY_G => self.board.height() as f32,
+                (false, _) => self.gravity,
+                (true, SoftDrop::Factor(factor)) => self.gravity * factor,
+                (true, SoftDrop::Rate(rate)) => rate.max(self.gravity),
+                (true, SoftDrop::Infinite) => self.board.height() as f32,
+            };
+            while self.fall_progress >= 1.0 {
+                self.fall_progress -= 1.0;
+                if self.board.try_move_piece_down(&mut piece) {
+                    self.stepped_down(piece.row);
+                    if self.soft_dropping {
+                        self.score += SOFT_DROP_POINTS;
+                    }
+                } else {
+                    self.fall_progress = 0.0;
+                }
+            }
+            self.current_piece = Some(piece);
+            if !self.is_grounded(piece) {
+                return;
+            }
+            self.lock_timer += 1;
+            let out_of_resets = self.config.lock_reset == LockReset::Move
+                && self.lock_resets >= self.config.max_lock_resets;
+            if self.lock_timer >= self.config.lock_delay || out_of_resets {
+                self.lock_piece(piece);
+            }
+        }
+    }
+    /// Applies a press or release of a [`GameAction`].
+    ///
+    /// Releases are always applied so held inputs never get stuck, while presses
+    /// only act in the states that respond to them.
+    pub fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Pressed(action) => self.held.insert(action),
+            InputEvent::Released(action) => self.held.remove(&action),
+        };
+        match (self.state, event) {
+            (GameState::Playing, InputEvent::Pressed(GameAction::Pause)) => {
+                self.state = GameState::Paused
+            }
+            (GameState::Paused, InputEvent::Pressed(GameAction::Pause)) => {
+                self.state = GameState::Playing
+            }
+            (GameState::Title, InputEvent::Pressed(action)) if action != GameAction::Pause => {
+                self.state = GameState::Ready(READY_TICKS)
+            }
+            (_, InputEvent::Pressed(GameAction::Restart)) => self.restart(),
+            (GameState::Playing, InputEvent::Pressed(action)) if self.current_piece.is_none() => {
+                self.buffer_action(action)
+            }
+            (GameState::Playing, InputEvent::Pressed(action)) => match action {
+                GameAction::MoveLeft => self.move_left(),
+                GameAction::MoveRight => self.move_right(),
+                GameAction::SoftDrop => self.start_soft_drop(),
+                GameAction::HardDrop => self.hard_drop(),
+                GameAction::RotateCW => self.rotate_clockwise(),
+                GameAction::RotateCCW => self.rotate_counterclockwise(),
+                GameAction::Rotate180 => self.rotate_180(),
+                GameAction::Hold => self.switch_hold(),
+                GameAction::Pause | GameAction::Restart => {}
+            },
+            (_, InputEvent::Released(action)) => match action {
+                GameAction::MoveLeft => self.release_left(),
+                GameAction::MoveRight => self.release_right(),
+                GameAction::SoftDrop => self.stop_soft_drop(),
+                _ => {}
+            },
+            (_, InputEvent::Pressed(_)) => {}
+        }
+    }
+    fn buffer_action(&mut self, action: GameAction) {
+        match action {
+            GameAction::MoveLeft => self.move_left(),
+            GameAction::MoveRight => self.move_right(),
+            GameAction::SoftDrop => self.start_soft_drop(),
+            GameAction::HardDrop
+            | GameAction::RotateCW
+            | GameAction::RotateCCW
+            | GameAction::Rotate180
+            | GameAction::Hold => self.buffered.push(action),
+            GameAction::Pause | GameAction::Restart => {}
+        }
+    }
+    fn replay_buffered(&mut self) {
+        for action in take(&mut self.buffered) {
+            let initial = match action {
+                GameAction::Hold => self.config.initial_hold,
+                GameAction::RotateCW | GameAction::RotateCCW | GameAction::Rotate180 => {
+                    self.config.initial_rotation
+                }
+                _ => false,
+            };
+            if initial && self.held.contains(&action) {
+                continue;
+            }
+            match action {
+                GameAction::HardDrop => self.hard_drop(),
+                GameAction::RotateCW => self.rotate_clockwise(),
+                GameAction::RotateCCW => self.rotate_counterclockwise(),
+                GameAction::Rotate180 => self.rotate_180(),
+                GameAction::Hold => self.switch_hold(),
+                _ => {}
+            }
+        }
+    }
+    fn hard_drop(&mut self) {
+        if let Some(mut piece) = self.current_piece {
+            let row = piece.row;
+            self.board.drop(&mut piece);
+            self.score += (row - piece.row) as u64 * HARD_DROP_POINTS;
+            self.lock_piece(piece);
+        }
+    }
+    fn start_soft_drop(&mut self) {
+        self.soft_dropping = true;
+        if let Some(mut piece) = self.current_piece {
+            if self.board.try_move_piece_down(&mut piece) {
+                self.stepped_down(piece.row);
+                self.score += SOFT_DROP_POINTS;
+            }
+            self.fall_instantly(&mut piece);
+            self.current_piece = Some(piece);
+        }
+    }
+    fn stop_soft_drop(&mut self) {
+        self.soft_dropping = false;
+    }
+    fn shift_direction(&self) -> Option<(Shift, bool)> {
+        match (self.shift_left, self.shift_right) {
+            (Some(left), Some(right)) => match self.config.das_priority {
+                DasPriority::Cancel => None,
+                DasPriority::LastPressed if right.order > left.order => Some((right, true)),
+                DasPriority::FirstPressed if right.order < left.order => Some((right, true)),
+                _ => Some((left, false)),
+            },
+            (Some(left), None) => Some((left, false)),
+            (None, Some(right)) => Some((right, true)),
+            (None, None) => None,
+        }
+    }
+    fn press_shift(&mut self) -> Option<Shift> {
+        self.shift_presses += 1;
+        Some(Shift {
+            order: self.shift_presses,
+            das_time: self.frame + self.config.das as u64,
+        })
+    }
+    fn move_left(&mut self) {
+        self.shift_left = self.press_shift();
+        if self.shift_direction().is_some_and(|(_, right)| !right) {
+            if let Some(mut piece) = self.current_piece {
+                if self.board.move_piece_left(&mut piece) {
+                    self.moved();
+                    self.fall_instantly(&mut piece);
+                }
+                self.current_piece = Some(piece);
+            }
+        }
+    }
+    fn move_right(&mut self) {
+        self.shift_right = self.press_shift();
+        if self.shift_direction().is_some_and(|(_, right)| right) {
+            if let Some(mut piece) = self.current_piece {
+                if self.board.move_piece_right(&mut piece) {
+                    self.moved();
+                    self.fall_instantly(&mut piece);
+                }
+                self.current_piece = Some(piece);
+            }
+        }
+    }
+    fn release_left(&mut self) {
+        self.shift_left = None;
+    }
+    fn release_right(&mut self) {
+        self.shift_right = None;
+    }
+    fn rotate_clockwise(&mut self) {
+        if let Some(mut piece) = self.current_piece {
+            if let Some(kick) = self.board.rotate_piece_clockwise(&mut piece) {
+                self.rotated(kick);
+                self.fall_instantly(&mut piece);
+            }
+            self.current_piece = Some(piece);
+        }
+    }
+    fn rotate_counterclockwise(&mut self) {
+        if let Some(mut piece) = self.current_piece {
+            if let Some(kick) = self.board.rotate_piece_counterclockwise(&mut piece) {
+                self.rotated(kick);
+                self.fall_instantly(&mut piece);
+            }
+            self.current_piece = Some(piece);
+        }
+    }
+    fn rotate_180(&mut self) {
+        if let Some(mut piece) = self.current_piece {
+            if let Some(kick) = self
+                .board
+                .rotate_piece_180(&mut piece, self.config.kicks_180)
+            {
+                self.rotated(kick);
+                self.fall_instantly(&mut piece);
+            }
+            self.current_piece = Some(piece);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity::GravityCurve;
+
+    fn playing(config: Config) -> Tetris {
+        let mut game = Tetris::with_config(0, config);
+        game.state = GameState::Playing;
+        game
+    }
+
+    impl Tetris {
+        fn sonic_drop(&mut self) {
+            let mut piece = self.current_piece.unwrap();
+            self.board.drop(&mut piece);
+            self.current_piece = Some(piece);
+        }
+    }
+
+    fn at(board: &Board, kind: PieceKind, left: i32, row: i32) -> Piece {
+        let piece = board.spawn_piece(kind);
+        let offset = piece
+            .filled(board.rotation_system())
+            .map(|(_, column)| column - piece.column)
+            .min()
+            .unwrap();
+        let bottom = board.rotation_system().bottom(kind, piece.rotation);
+        Piece {
+            column: left - offset,
+            row: row - bottom,
+            ..piece
+        }
+    }
+
+    fn fill(board: &mut Board, kind: PieceKind, left: i32, row: i32) {
+        let piece = at(board, kind, left, row);
+        board.place_unchecked(piece);
+    }
+
+    fn pieces(seed: u64) -> Vec<PieceKind> {
+        let mut game = Tetris::with_seed(seed);
+        (0..21).map(|_| game.next_piece()).collect()
+    }
+
+    #[test]
+    fn same_seed_deals_same_pieces() {
+        assert_eq!(pieces(42), pieces(42));
+        assert_ne!(pieces(42), pieces(43));
+    }
+
+    #[test]
+    fn every_bag_holds_each_piece_once() {
+        for bag in pieces(42).chunks(7) {
+            let mut bag = bag.to_vec();
+            bag.sort_by_key(|kind| kind.index());
+            assert_eq!(bag, PieceKind::TETROMINOES);
+        }
+    }
+
+    fn replay(seed: u64) -> String {
+        let mut game = Tetris::with_seed(seed);
+        let script = [
+            GameAction::MoveLeft,
+            GameAction::RotateCW,
+            GameAction::HardDrop,
+            GameAction::MoveRight,
+            GameAction::Hold,
+            GameAction::HardDrop,
+        ];
+        game.handle_input(InputEvent::Pressed(GameAction::HardDrop));
+        for tick in 0..600 {
+            if tick >= READY_TICKS && tick % 10 == 0 {
+                let action = script[(tick / 10) as usize % script.len()];
+                game.handle_input(InputEvent::Pressed(action));
+                game.handle_input(InputEvent::Released(action));
+            }
+            game.update();
+        }
+        let rows: Vec<Vec<_>> = game
+            .board
+            .rows()
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.filled).collect())
+            .collect();
+        format!(
+            "{} {:?} {:?} {:?}",
+            game.frame, game.current_piece, game.hold, rows
+        )
+    }
+
+    #[test]
+    fn same_inputs_on_same_ticks_replay_the_same_game() {
+        assert_eq!(replay(42), replay(42));
+        assert_ne!(replay(42), replay(43));
+    }
+
+    fn filled_cells(game: &Tetris) -> usize {
+        game.board
+            .rows()
+            .iter()
+            .flatten()
+            .filter(|cell| cell.filled.is_some())
+            .count()
+    }
+
+    fn tap(game: &mut Tetris, action: GameAction) {
+        game.handle_input(InputEvent::Pressed(action));
+        game.handle_input(InputEvent::Released(action));
+    }
+
+    fn run(game: &mut Tetris, ticks: u32) {
+        for _ in 0..ticks {
+            game.update();
+        }
+    }
+
+    #[test]
+    fn held_hard_drop_buffered_during_entry_delay_still_drops() {
+        let mut game = playing(Config {
+            gravity: GravityCurve::Fixed(0.0),
+            initial_rotation: true,
+            entry_delay: 10,
+            ..Config::default()
+        });
+        run(&mut game, 1);
+        tap(&mut game, GameAction::HardDrop);
+        assert!(game.current_piece.is_none());
+        game.handle_input(InputEvent::Pressed(GameAction::HardDrop));
+        run(&mut game, 11);
+        assert_eq!(filled_cells(&game), 8);
+    }
+
+    #[test]
+    fn held_rotation_buffered_during_entry_delay_turns_once() {
+        let mut game = playing(Config {
+            gravity: GravityCurve::Fixed(0.0),
+            initial_rotation: true,
+            entry_delay: 10,
+            ..Config::default()
+        });
+        run(&mut game, 1);
+        tap(&mut game, GameAction::HardDrop);
+        game.handle_input(InputEvent::Pressed(GameAction::RotateCW));
+        run(&mut game, 11);
+        let piece = game.current_piece.unwrap();
+        let spawn = game.board.rotation_system().spawn_rotation(piece.kind);
+        assert_eq!(piece.rotation, (spawn + 1) % 4);
+    }
+
+    #[test]
+    fn inputs_during_line_clear_delay_apply_to_the_next_piece() {
+        let mut game = playing(Config {
+            width: 8,
+            gravity: GravityCurve::Fixed(0.0),
+            line_clear_delay: 10,
+            ..Config::default()
+        });
+        fill(&mut game.board, PieceKind::O, 0, 0);
+        fill(&mut game.board, PieceKind::O, 6, 0);
+        game.spawn_piece(PieceKind::I);
+        tap(&mut game, GameAction::HardDrop);
+        assert!(game.is_clearing_lines());
+        tap(&mut game, GameAction::RotateCW);
+        run(&mut game, 9);
+        assert!(game.current_piece.is_none());
+        run(&mut game, 2);
+        assert!(!game.is_clearing_lines());
+        assert_eq!(game.lines_cleared(), 1);
+        let piece = game.current_piece.unwrap();
+        let spawn = game.board.rotation_system().spawn_rotation(piece.kind);
+        assert_eq!(piece.rotation, (spawn + 1) % 4);
+    }
+
+    fn column(game: &Tetris) -> i32 {
+        game.current_piece.unwrap().column
+    }
+
+    #[test]
+    fn auto_shift_starts_after_das_and_repeats_every_arr() {
+        let mut game = playing(Config {
+            gravity: GravityCurve::Fixed(0.0),
+            das: 10,
+            arr: 2,
+            ..Config::default()
+        });
+        game.spawn_piece(PieceKind::T);
+        let start = column(&game);
+        game.handle_input(InputEvent::Pressed(GameAction::MoveLeft));
+        assert_eq!(column(&game), start - 1);
+        run(&mut game, 9);
+        assert_eq!(column(&game), start - 1);
+        run(&mut game, 1);
+        assert_eq!(column(&game), start - 2);
+        run(&mut game, 1);
+        assert_eq!(column(&game), start - 2);
+        run(&mut game, 1);
+        assert_eq!(column(&game), start - 3);
+    }
+
+    #[test]
+    fn zero_arr_shifts_to_the_wall_once_das_charges() {
+        let mut game = playing(Config {
+            gravity: GravityCurve::Fixed(0.0),
+            das: 5,
+            arr: 0,
+            ..Config::default()
+        });
+        game.spawn_piece(PieceKind::T);
+        let start = column(&game);
+        game.handle_input(InputEvent::Pressed(GameAction::MoveRight));
+        run(&mut game, 4);
+        assert_eq!(column(&game), start + 1);
+        run(&mut game, 1);
+        let mut piece = game.current_piece.unwrap();
+        assert!(!game.board.move_piece_right(&mut piece));
+    }
+
+    #[test]
+    fn rotating_holds_off_a_charged_shift_for_das_cut() {
+        let mut game = playing(Config {
+            gravity: GravityCurve::Fixed(0.0),
+            das: 4,
+            arr: 1,
+            das_cut: 6,
+            ..Config::default()
+        });
+        game.spawn_piece(PieceKind::T);
+        let start = column(&game);
+        game.handle_input(InputEvent::Pressed(GameAction::MoveLeft));
+        run(&mut game, 4);
+        assert_eq!(column(&game), start - 2);
+        tap(&mut game, GameAction::RotateCW);
+        run(&mut game, 5);
+        assert_eq!(column(&game), start - 2);
+        run(&mut game, 1);
+        assert_eq!(column(&game), start - 3);
+    }
+
+    // Holds left until it auto shifts, then presses and releases right,
+    // returning the columns moved by the right press, while both are held, and
+    // on the tick after right is released.
+    fn press_right_over_left(das_priority: DasPriority) -> (i32, i32, i32) {
+        let mut game = playing(Config {
+            width: 20,
+            gravity: GravityCurve::Fixed(0.0),
+            das: 4,
+            arr: 1,
+            das_priority,
+            ..Config::default()
+        });
+        game.spawn_piece(PieceKind::T);
+        game.handle_input(InputEvent::Pressed(GameAction::MoveLeft));
+        run(&mut game, 4);
+        let charged = column(&game);
+        game.handle_input(InputEvent::Pressed(GameAction::MoveRight));
+        let pressed = column(&game);
+        run(&mut game, 4);
+        let both = column(&game);
+        game.handle_input(InputEvent::Released(GameAction::MoveRight));
+        run(&mut game, 1);
+        (pressed - charged, both - pressed, column(&game) - both)
+    }
+
+    #[test]
+    fn last_pressed_direction_wins_until_released() {
+        assert_eq!(press_right_over_left(DasPriority::LastPressed), (1, 1, -1));
+    }
+
+    #[test]
+    fn both_directions_held_cancel_until_one_is_released() {
+        assert_eq!(press_right_over_left(DasPriority::Cancel), (0, 0, -1));
+    }
+
+    #[test]
+    fn first_pressed_direction_keeps_shifting() {
+        assert_eq!(
+            press_right_over_left(DasPriority::FirstPressed),
+            (0, -4, -1)
+        );
+    }
+
+    #[test]
+    fn charged_shift_carries_over_to_the_next_piece() {
+        let mut game = playing(Config {
+            gravity: GravityCurve::Fixed(0.0),
+            das: 4,
+            arr: 0,
+            ..Config::default()
+        });
+        game.spawn_piece(PieceKind::T);
+        game.handle_input(InputEvent::Pressed(GameAction::MoveLeft));
+        run(&mut game, 4);
+        tap(&mut game, GameAction::HardDrop);
+        run(&mut game, 1);
+        let mut piece = game.current_piece.unwrap();
+        assert!(!game.board.move_piece_left(&mut piece));
+    }
+
+    const INITIAL_TURNS: [(GameAction, u8); 3] = [
+        (GameAction::Rotate180, 2),
+        (GameAction::RotateCW, 1),
+        (GameAction::RotateCCW, 3),
+    ];
+
+    #[test]
+    fn held_rotation_turns_the_piece_as_it_spawns() {
+        for &(action, turn) in &INITIAL_TURNS {
+            let mut game = playing(Config {
+                initial_rotation: true,
+                ..Config::default()
+            });
+            game.handle_input(InputEvent::Pressed(action));
+            game.spawn_piece(PieceKind::T);
+            assert_eq!(game.current_piece.unwrap().rotation, turn);
+        }
+    }
+
+    #[test]
+    fn initial_rotation_is_skipped_when_the_turned_piece_collides() {
+        for &(action, _) in &INITIAL_TURNS {
+            let mut game = playing(Config {
+                initial_rotation: true,
+                ..Config::default()
+            });
+            let spawn = game.board.spawn_piece(PieceKind::T);
+            fill(
+                &mut game.board,
+                PieceKind::O,
+                spawn.column + 1,
+                spawn.row - 1,
+            );
+            game.handle_input(InputEvent::Pressed(action));
+            game.spawn_piece(PieceKind::T);
+            assert_eq!(game.top_out, None);
+            assert_eq!(game.current_piece.unwrap().rotation, spawn.rotation);
+        }
+    }
+
+    fn spawn_holding(hold: Option<PieceKind>) -> (Tetris, Vec<PieceKind>) {
+        let mut game = playing(Config {
+            initial_hold: true,
+            entry_delay: 0,
+            line_clear_delay: 0,
+            ..Config::default()
+        });
+        run(&mut game, 1);
+        tap(&mut game, GameAction::HardDrop);
+        game.hold.1 = hold;
+        let next = game.queue().take(2).collect();
+        game.handle_input(InputEvent::Pressed(GameAction::Hold));
+        run(&mut game, 1);
+        (game, next)
+    }
+
+    #[test]
+    fn initial_hold_into_an_empty_slot_deals_the_piece_after() {
+        let (game, next) = spawn_holding(None);
+        assert_eq!(game.current_piece.unwrap().kind, next[1]);
+        assert_eq!(game.hold, (true, Some(next[0])));
+    }
+
+    #[test]
+    fn initial_hold_swaps_in_the_held_piece() {
+        let (game, next) = spawn_holding(Some(PieceKind::O));
+        assert_eq!(game.current_piece.unwrap().kind, PieceKind::O);
+        assert_eq!(game.hold, (true, Some(next[0])));
+        assert_eq!(game.queue().next(), Some(next[1]));
+    }
+
+    #[test]
+    fn spawning_into_the_stack_blocks_out() {
+        let mut game = playing(Config::default());
+        let spawn = game.board.spawn_piece(PieceKind::T);
+        fill(&mut game.board, PieceKind::O, spawn.column, spawn.row + 1);
+        game.spawn_piece(PieceKind::T);
+        assert_eq!(game.top_out, Some(TopOut::BlockOut));
+        assert_eq!(game.state, GameState::ToppedOut(RESULTS_DELAY_TICKS));
+    }
+
+    #[test]
+    fn locking_above_the_visible_rows_locks_out() {
+        let mut game = playing(Config::default());
+        let mut piece = game.board.spawn_piece(PieceKind::T);
+        piece.row = game.board.visible_height() as i32;
+        game.current_piece = Some(piece);
+        game.lock_piece(piece);
+        assert_eq!(game.top_out, Some(TopOut::LockOut));
+        assert_eq!(game.state, GameState::ToppedOut(RESULTS_DELAY_TICKS));
+    }
+
+    fn lock_boxed_in_o(all_spins: bool, last_kick: Option<(i8, i8)>) -> LockResult {
+        let mut game = playing(Config {
+            all_spins,
+            ..Config::default()
+        });
+        fill(&mut game.board, PieceKind::O, 2, 0);
+        fill(&mut game.board, PieceKind::O, 0, 2);
+        let piece = at(&game.board, PieceKind::O, 0, 0);
+        game.current_piece = Some(piece);
+        game.last_kick = last_kick;
+        game.lock_piece(piece);
+        game.last_lock.unwrap()
+    }
+
+    #[test]
+    fn rotating_into_an_immobile_spot_is_an_all_spin() {
+        assert!(lock_boxed_in_o(true, Some((0, 0))).all_spin);
+        assert!(!lock_boxed_in_o(true, None).all_spin);
+        assert!(!lock_boxed_in_o(false, Some((0, 0))).all_spin);
+    }
+
+    fn drop_and_lock(game: &mut Tetris, rotation: u8, left: i32) -> LockResult {
+        let mut piece = Piece {
+            rotation,
+            ..game.board.spawn_piece(PieceKind::I)
+        };
+        let offset = piece
+            .filled(game.board.rotation_system())
+            .map(|(_, column)| column - piece.column)
+            .min()
+            .unwrap();
+        piece.column = left - offset;
+        game.board.drop(&mut piece);
+        game.current_piece = Some(piece);
+        game.lock_piece(piece);
+        game.last_lock.unwrap()
+    }
+
+    fn tetris(game: &mut Tetris) -> LockResult {
+        for left in 0..3 {
+            assert_eq!(drop_and_lock(game, 1, left).lines_cleared, 0);
+        }
+        drop_and_lock(game, 1, 3)
+    }
+
+    #[test]
+    fn locks_track_combos_and_back_to_back_chains() {
+        let mut game = playing(Config {
+            width: 4,
+            line_clear_delay: 0,
+            ..Config::default()
+        });
+        let first = tetris(&mut game);
+        assert_eq!(
+            (first.lines_cleared, first.combo, first.back_to_back),
+            (4, 1, 0)
+        );
+        assert_eq!(game.back_to_back(), 1);
+        let second = tetris(&mut game);
+        assert_eq!((second.combo, second.back_to_back), (1, 1));
+        assert_eq!(game.back_to_back(), 2);
+        let single = drop_and_lock(&mut game, 0, 0);
+        assert_eq!(
+            (single.lines_cleared, single.combo, single.back_to_back),
+            (1, 2, 0)
+        );
+        assert_eq!(game.back_to_back(), 0);
+        let single = drop_and_lock(&mut game, 0, 0);
+        assert_eq!(single.combo, 3);
+        assert_eq!(game.combo(), 3);
+        assert_eq!(drop_and_lock(&mut game, 1, 0).combo, 0);
+        assert_eq!(game.combo(), 0);
+        assert_eq!(game.lines_cleared(), 10);
+    }
+
+    #[test]
+    fn restart_only_replays_a_requested_seed() {
+        let mut game = playing(Config::default());
+        game.handle_input(InputEvent::Pressed(GameAction::Restart));
+        assert_eq!(game.seed(), 0);
+
+        let mut game = Tetris::with_random_seed(Config::default());
+        game.state = GameState::Playing;
+        let seed = game.seed();
+        game.handle_input(InputEvent::Pressed(GameAction::Restart));
+        assert_ne!(game.seed(), seed);
+    }
+
+    #[test]
+    fn every_move_on_the_stack_uses_a_reset() {
+        let mut game = playing(Config {
+            gravity: GravityCurve::Fixed(0.0),
+            ..Config::default()
+        });
+        game.spawn_piece(PieceKind::T);
+        game.sonic_drop();
+        for _ in 0..3 {
+            game.handle_input(InputEvent::Pressed(GameAction::MoveLeft));
+            game.handle_input(InputEvent::Pressed(GameAction::MoveRight));
+        }
+        assert_eq!(game.lock_resets, 6);
+    }
+
+    #[test]
+    fn twenty_g_piece_falls_into_gap_it_shifts_over() {
+        let mut game = playing(Config {
+            gravity: GravityCurve::Fixed(TWENTY_G),
+            das: 0,
+            arr: 0,
+            ..Config::default()
+        });
+        for row in 0..4 {
+            fill(&mut game.board, PieceKind::I, 2, row);
+            fill(&mut game.board, PieceKind::I, 6, row);
+        }
+        game.spawn_piece(PieceKind::O);
+        let landed = game.current_piece.unwrap();
+        assert_eq!(
+            game.board.rotation_system().bottom(PieceKind::O, 0) + landed.row,
+            4
+        );
+
+        game.handle_input(InputEvent::Pressed(GameAction::MoveLeft));
+        game.update();
+        let piece = game.current_piece.unwrap();
+        let cells: Vec<_> = piece.filled(game.board.rotation_system()).collect();
+        assert!(cells.iter().all(|&(row, column)| row < 2 && column < 2));
+    }
+}
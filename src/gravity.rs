@@ -0,0 +1,141 @@
+use crate::game::TICKS_PER_SECOND;
+
+/// Gravity at or above which pieces drop to the stack the moment they appear.
+pub const TWENTY_G: f32 = 20.0;
+
+const NES_FRAMES_PER_ROW: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, // levels 0-9
+    5, 5, 5, 4, 4, 4, 3, 3, 3, 2, // levels 10-19
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 1, // levels 20-29
+];
+
+const TGM_INTERNAL_GRAVITY: [(u32, u32); 30] = [
+    (0, 4),
+    (30, 6),
+    (35, 8),
+    (40, 10),
+    (50, 12),
+    (60, 16),
+    (70, 32),
+    (80, 48),
+    (90, 64),
+    (100, 80),
+    (120, 96),
+    (140, 112),
+    (160, 128),
+    (170, 144),
+    (200, 4),
+    (220, 32),
+    (230, 64),
+    (233, 96),
+    (236, 128),
+    (239, 160),
+    (243, 192),
+    (247, 224),
+    (251, 256),
+    (300, 512),
+    (330, 768),
+    (360, 1024),
+    (400, 1280),
+    (420, 1024),
+    (450, 768),
+    (500, 5120),
+];
+
+/// How fast pieces fall at each level, in rows per tick (G).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GravityCurve {
+    /// The guideline formula, where a row takes `(0.8 - (level - 1) * 0.007)`
+    /// to the power of `level - 1` seconds. Reaches 20G at level 19.
+    Guideline,
+    /// The NTSC NES table, by frames per row. Tops out at one row per tick from
+    /// level 29.
+    Nes,
+    /// The internal gravity of the first Tetris: The Grand Master, in 256ths of
+    /// a row per tick. Reaches 20G at level 500.
+    Tgm,
+    /// The same gravity at every level.
+    Fixed(f32),
+}
+
+impl GravityCurve {
+    /// Rows per tick at `level`.
+    pub fn gravity(self, level: u32) -> f32 {
+        match self {
+            GravityCurve::Guideline => {
+                let steps = level.clamp(1, 20) as f32 - 1.0;
+                let seconds = (0.8 - steps * 0.007).powf(steps);
+                (1.0 / (seconds * TICKS_PER_SECOND as f32)).min(TWENTY_G)
+            }
+            GravityCurve::Nes => {
+                1.0 / NES_FRAMES_PER_ROW[(level as usize).min(NES_FRAMES_PER_ROW.len() - 1)] as f32
+            }
+            GravityCurve::Tgm => {
+                let internal = TGM_INTERNAL_GRAVITY
+                    .iter()
+                    .rev()
+                    .find(|&&(threshold, _)| threshold <= level)
+                    .map_or(4, |&(_, internal)| internal);
+                internal as f32 / 256.0
+            }
+            GravityCurve::Fixed(gravity) => gravity,
+        }
+    }
+}
+
+/// How the level rises during a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelProgression {
+    /// One level for every this many lines cleared.
+    Lines(u32),
+    /// The Grand Master's counter: one level for every piece that enters and
+    /// one for every line cleared, except that pieces cannot take the level past
+    /// the end of a hundred or past 998. The level stops at 999.
+    Tgm,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tgm_internal(level: u32) -> f32 {
+        GravityCurve::Tgm.gravity(level) * 256.0
+    }
+
+    #[test]
+    fn tgm_gravity_matches_the_original() {
+        let expected = [
+            (0, 4),
+            (29, 4),
+            (30, 6),
+            (35, 8),
+            (40, 10),
+            (199, 144),
+            (200, 4),
+            (251, 256),
+            (300, 512),
+            (450, 768),
+            (499, 768),
+            (500, 5120),
+            (999, 5120),
+        ];
+        for &(level, internal) in &expected {
+            assert_eq!(tgm_internal(level), internal as f32, "level {}", level);
+        }
+    }
+
+    #[test]
+    fn tgm_table_is_sorted_by_level() {
+        assert!(TGM_INTERNAL_GRAVITY
+            .windows(2)
+            .all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn guideline_and_nes_curves() {
+        assert!((GravityCurve::Guideline.gravity(1) - 1.0 / 60.0).abs() < 1e-6);
+        assert_eq!(GravityCurve::Guideline.gravity(20), TWENTY_G);
+        assert_eq!(GravityCurve::Nes.gravity(0), 1.0 / 48.0);
+        assert_eq!(GravityCurve::Nes.gravity(40), 1.0);
+    }
+}
@@ -0,0 +1,24 @@
+/// A player input, independent of the device that produced it.
+///
+/// Keyboards, gamepads, replays, bots and network peers all drive the engine by
+/// feeding these to [`Tetris::handle_input`](crate::Tetris::handle_input).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameAction {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateCW,
+    RotateCCW,
+    Rotate180,
+    Hold,
+    Pause,
+    Restart,
+}
+
+/// A [`GameAction`] being pressed or released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputEvent {
+    Pressed(GameAction),
+    Released(GameAction),
+}
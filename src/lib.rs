@@ -0,0 +1,32 @@
+//! A headless Tetris rules engine.
+//!
+//! The engine has no dependency on any windowing or rendering library, so it can
+//! be used to build bots, tools and tests as well as graphical frontends. The
+//! ggez frontend in `src/main.rs` is one such consumer.
+//!
+//! `cargo build` and `cargo test` build only the engine. The frontend is behind
+//! the `frontend` feature: `cargo run --features frontend`.
+
+pub mod attack;
+pub mod board;
+pub mod config;
+mod filled;
+pub mod game;
+pub mod gravity;
+pub mod input;
+pub mod piece;
+pub mod piece_set;
+pub mod rotation;
+pub mod score;
+pub mod state;
+
+pub use attack::{AttackTable, ComboAttack};
+pub use board::{Board, Cell, LockResult, TSpin};
+pub use config::{Config, DasPriority, LockReset, SoftDrop};
+pub use game::Tetris;
+pub use gravity::{GravityCurve, LevelProgression};
+pub use input::{GameAction, InputEvent};
+pub use piece::{Kicks180, Piece, PieceBlockIter, PieceKind};
+pub use piece_set::{PieceDef, PieceSet, PieceSetError};
+pub use rotation::{Ars, Nrs, RotationSystem, Srs};
+pub use state::{GameResults, GameState, TopOut};
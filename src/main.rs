@@ -1,466 +1,196 @@
-use filled::{FILLED, ROTATION_OFFSETS};
+use ggez::conf::WindowMode;
 use ggez::event::{self, EventHandler, KeyCode, KeyMods};
 use ggez::graphics;
 use ggez::graphics::{window, Color, DrawParam, Rect};
-use ggez::{Context, ContextBuilder, GameResult};
-use num_derive::FromPrimitive;
-use num_traits::cast::FromPrimitive;
-use rand::prelude::{SliceRandom, ThreadRng};
-use rand::Rng;
-use std::cmp::min;
-use std::mem::swap;
-use std::time::{Duration, Instant};
-
-mod filled;
-
-#[derive(Debug, Clone, Copy)]
-struct Piece {
-    kind: Tetromino,
-    column: u8,
-    row: u8,
-    rotation: u8,
-}
-
-#[derive(Debug, Clone, Copy)]
-struct PieceBlockIter<'a> {
-    block_kind: &'a [(u8, u8)],
-    column: u8,
-    row: u8,
-}
+use ggez::{timer, Context, ContextBuilder, GameResult};
+use std::sync::Arc;
+use std::time::Duration;
+use tetris::game::TICKS_PER_SECOND;
+use tetris::{
+    Ars, AttackTable, Board, Config, DasPriority, GameAction, GameState, GravityCurve, InputEvent,
+    Kicks180, LevelProgression, LockReset, LockResult, Nrs, Piece, PieceSet, RotationSystem,
+    SoftDrop, Srs, TSpin, Tetris, TopOut,
+};
 
-impl<'a> Iterator for PieceBlockIter<'a> {
-    type Item = (u8, u8);
+const PEEK_HEIGHT: f32 = 8.0;
 
-    fn next(&mut self) -> Option<(u8, u8)> {
-        let ((x, y), remaining) = self.block_kind.split_first()?;
-        self.block_kind = remaining;
-        Some((*x + self.row, *y + self.column))
+fn draw_cell(
+    ctx: &mut Context,
+    (row, column): (usize, usize),
+    (x, y): (f32, f32),
+    visible_height: usize,
+    color: Color,
+) -> GameResult {
+    if row > visible_height {
+        return Ok(());
     }
+    let mut rect = Rect::new(
+        x + 1.0 + 16.0 * column as f32,
+        y + 1.0 - 16.0 * (row + 1) as f32,
+        14.0,
+        14.0,
+    );
+    if row == visible_height {
+        let top = y - 16.0 * visible_height as f32 - PEEK_HEIGHT;
+        rect.h -= top - rect.y;
+        rect.y = top;
+    }
+    let rectangle = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), rect, color)?;
+    graphics::draw(ctx, &rectangle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
 }
 
-impl Piece {
-    fn filled(&self) -> PieceBlockIter {
-        PieceBlockIter {
-            block_kind: self.kind.filled(self.rotation),
-            column: self.column,
-            row: self.row,
-        }
-    }
-    fn draw_ggez(self, ctx: &mut Context, x: f32, y: f32) -> GameResult {
-        for (ypos, xpos) in self.filled() {
-            let rectangle = graphics::Mesh::new_rectangle(
-                ctx,
-                graphics::DrawMode::fill(),
-                Rect::new(
-                    x + 1.0 + 16.0 * xpos as f32,
-                    y + 1.0 - 16.0 * (ypos + 1) as f32,
-                    14.0,
-                    14.0,
-                ),
-                self.kind.color(),
-            )?;
-            graphics::draw(ctx, &rectangle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
-        }
-        Ok(())
-    }
-    fn draw_ghost_ggez(self, ctx: &mut Context, x: f32, y: f32) -> GameResult {
-        for (ypos, xpos) in self.filled() {
-            let mut ghost_color = self.kind.color();
-            ghost_color.a = 0.2;
-            let rectangle = graphics::Mesh::new_rectangle(
-                ctx,
-                graphics::DrawMode::fill(),
-                Rect::new(
-                    x + 1.0 + 16.0 * xpos as f32,
-                    y + 1.0 - 16.0 * (ypos + 1) as f32,
-                    14.0,
-                    14.0,
-                ),
-                ghost_color,
-            )?;
-            graphics::draw(ctx, &rectangle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
-        }
-        Ok(())
+fn draw_piece(
+    ctx: &mut Context,
+    system: &dyn RotationSystem,
+    piece: Piece,
+    origin: (f32, f32),
+    visible_height: usize,
+    color: Color,
+) -> GameResult {
+    for (ypos, xpos) in piece.filled(system) {
+        draw_cell(
+            ctx,
+            (ypos as usize, xpos as usize),
+            origin,
+            visible_height,
+            color,
+        )?;
     }
+    Ok(())
 }
 
-#[derive(Debug, FromPrimitive, Clone, Copy)]
-enum Tetromino {
-    O,
-    T,
-    L,
-    J,
-    S,
-    Z,
-    I,
+fn draw_piece_ggez(
+    ctx: &mut Context,
+    system: &dyn RotationSystem,
+    piece: Piece,
+    origin: (f32, f32),
+    visible_height: usize,
+) -> GameResult {
+    draw_piece(
+        ctx,
+        system,
+        piece,
+        origin,
+        visible_height,
+        system.color(piece.kind).into(),
+    )
 }
 
-impl Tetromino {
-    fn filled(&self, rotation: u8) -> &[(u8, u8)] {
-        match self {
-            Self::O => &FILLED[rotation as usize][0..4],
-            Self::T => &FILLED[rotation as usize][4..8],
-            Self::L => &FILLED[rotation as usize][8..12],
-            Self::J => &FILLED[rotation as usize][12..16],
-            Self::S => &FILLED[rotation as usize][16..20],
-            Self::Z => &FILLED[rotation as usize][20..24],
-            Self::I => &FILLED[rotation as usize][24..28],
-        }
-    }
-    fn height(self, rotation: u8) -> usize {
-        if rotation % 2 == 0 {
-            match self {
-                Self::I => 1,
-                _ => 2,
-            }
-        } else {
-            match self {
-                Self::O => 2,
-                Self::I => 4,
-                _ => 3,
-            }
-        }
-    }
-    fn width(self, rotation: u8) -> usize {
-        if rotation % 2 == 0 {
-            match self {
-                Self::O => 2,
-                Self::I => 4,
-                _ => 3,
-            }
-        } else {
-            match self {
-                Self::I => 1,
-                _ => 2,
+fn draw_ghost_ggez(
+    ctx: &mut Context,
+    system: &dyn RotationSystem,
+    piece: Piece,
+    origin: (f32, f32),
+    visible_height: usize,
+) -> GameResult {
+    let mut ghost_color: Color = system.color(piece.kind).into();
+    ghost_color.a = 0.2;
+    draw_piece(ctx, system, piece, origin, visible_height, ghost_color)
+}
+
+fn draw_board_ggez(ctx: &mut Context, board: &Board, origin: (f32, f32)) -> GameResult {
+    let visible_height = board.visible_height();
+    let system = board.rotation_system();
+    for (ypos, row) in board.rows().iter().enumerate().take(visible_height + 1) {
+        for (xpos, cell) in row.iter().enumerate() {
+            if let Some(kind) = cell.filled {
+                draw_cell(
+                    ctx,
+                    (ypos, xpos),
+                    origin,
+                    visible_height,
+                    system.color(kind).into(),
+                )?;
             }
         }
     }
-    fn color(self) -> Color {
-        match self {
-            Self::O => (255, 255, 0),
-            Self::T => (255, 0, 255),
-            Self::L => (255, 127, 0),
-            Self::J => (63, 63, 255),
-            Self::S => (63, 255, 63),
-            Self::Z => (255, 0, 0),
-            Self::I => (0, 255, 255),
-        }
-        .into()
-    }
+    Ok(())
 }
 
-#[derive(Default, Debug, Copy, Clone)]
-struct Cell {
-    filled: Option<Tetromino>,
+fn format_time(time: Duration) -> String {
+    format!(
+        "{}:{:0>2}.{:0>3}",
+        time.as_secs() / 60,
+        time.as_secs() % 60,
+        time.subsec_millis()
+    )
 }
 
-#[derive(Default, Debug)]
-struct Board {
-    board: [[Cell; 10]; 20],
+fn state_banner(game: &Tetris) -> Option<String> {
+    match game.state() {
+        GameState::Title => Some("Press any key".to_string()),
+        GameState::Ready(ticks) => Some((ticks / TICKS_PER_SECOND + 1).to_string()),
+        GameState::Playing => None,
+        GameState::Paused => Some("Paused".to_string()),
+        GameState::Finished(_) => Some("Clear!".to_string()),
+        GameState::ToppedOut(_) => Some("Game Over".to_string()),
+        GameState::Results => game.results().map(|results| {
+            let outcome = match results.top_out {
+                None => format_time(results.time),
+                Some(TopOut::BlockOut) => "Block Out".to_string(),
+                Some(TopOut::LockOut) => "Lock Out".to_string(),
+            };
+            format!(
+                "{}\nScore {}\nAttack {}\nPerfect clears {}\nR to retry",
+                outcome, results.score, results.attack, results.perfect_clears
+            )
+        }),
+    }
 }
 
-impl Board {
-    fn check_collision(&self, piece: Piece) -> bool {
-        let mut collides = false;
-        for (x, y) in piece.filled() {
-            collides |= match self.board.get(x as usize).map(|x| {
-                if let Some(z) = x.get(y as usize) {
-                    z.filled.is_some()
-                } else {
-                    true
-                }
-            }) {
-                Some(val) => val,
-                None => continue,
-            }
-        }
-        collides
+fn lock_callout(result: LockResult) -> Option<String> {
+    let spin = match result.t_spin {
+        TSpin::None if result.all_spin => "All-Spin ",
+        TSpin::None => "",
+        TSpin::Mini => "T-Spin Mini ",
+        TSpin::Full => "T-Spin ",
+    };
+    let lines = match result.lines_cleared {
+        0 if spin.is_empty() => return None,
+        0 => "",
+        1 => "Single",
+        2 => "Double",
+        3 => "Triple",
+        4 => "Quad",
+        _ => "Clear",
+    };
+    let mut callout = format!("{}{}", spin, lines).trim_end().to_string();
+    if result.back_to_back > 0 {
+        callout = format!("B2B x{} {}", result.back_to_back, callout);
     }
-    fn move_piece_left(&self, piece: &mut Piece) -> bool {
-        if piece.column == 0 {
-            return false;
-        }
-        piece.column -= 1;
-        if self.check_collision(*piece) {
-            piece.column += 1;
-            false
-        } else {
-            true
-        }
+    if result.perfect_clear {
+        callout = format!("{}\nPerfect Clear", callout);
     }
-    fn move_piece_right(&self, piece: &mut Piece) -> bool {
-        if piece.column >= 10 - piece.kind.width(piece.rotation as u8) as u8 {
-            return false;
-        }
-        piece.column += 1;
-        if self.check_collision(*piece) {
-            piece.column -= 1;
-            false
-        } else {
-            true
-        }
-    }
-    fn das_left(&self, piece: &mut Piece) {
-        while self.move_piece_left(piece) {}
-    }
-    fn das_right(&self, piece: &mut Piece) {
-        while self.move_piece_right(piece) {}
-    }
-    fn move_piece_down(&mut self, piece: &mut Piece) -> Option<isize> {
-        if piece.row == 0 {
-            Some(self.place_unchecked(*piece))
-        } else {
-            piece.row -= 1;
-            if self.check_collision(*piece) {
-                piece.row += 1;
-                Some(self.place_unchecked(*piece))
-            } else {
-                None
-            }
-        }
-    }
-    fn rotate_piece_clockwise(&self, piece: &mut Piece) {
-        let (orginal_x, original_y) =
-            ROTATION_OFFSETS[piece.kind as usize][piece.rotation as usize];
-        piece.rotation = (piece.rotation + 1) % 4;
-        let (new_x, new_y) = ROTATION_OFFSETS[piece.kind as usize][piece.rotation as usize];
-        piece.row += new_y;
-        piece.column += new_x;
-        piece.row = min(
-            piece.row.saturating_sub(original_y),
-            20 - piece.kind.height(piece.rotation) as u8,
-        );
-        piece.column = min(
-            piece.column.saturating_sub(orginal_x),
-            10 - piece.kind.width(piece.rotation) as u8,
-        );
-        if self.check_collision(*piece) {
-            piece.rotation = (piece.rotation + 3) % 4;
-        }
-    }
-    fn rotate_piece_counterclockwise(&self, piece: &mut Piece) {
-        let (orginal_x, original_y) =
-            ROTATION_OFFSETS[piece.kind as usize][piece.rotation as usize];
-        piece.rotation = (piece.rotation + 3) % 4;
-        let (new_x, new_y) = ROTATION_OFFSETS[piece.kind as usize][piece.rotation as usize];
-        piece.row += new_y;
-        piece.column += new_x;
-        piece.row = min(
-            piece.row.saturating_sub(original_y),
-            20 - piece.kind.height(piece.rotation) as u8,
-        );
-        piece.column = min(
-            piece.column.saturating_sub(orginal_x),
-            10 - piece.kind.width(piece.rotation) as u8,
-        );
-        if self.check_collision(*piece) {
-            piece.rotation = (piece.rotation + 1) % 4;
-        }
-    }
-    fn drop(&self, piece: &mut Piece) -> bool {
-        if piece.column >= 10 {
-            return false;
-        }
-        while piece.row > 0 {
-            piece.row -= 1;
-            if self.check_collision(*piece) {
-                if piece.row < 20 - piece.kind.height(piece.rotation) as u8 {
-                    piece.row += 1;
-                    break;
-                } else {
-                    return false;
-                }
-            }
-        }
-        true
-    }
-    fn hard_drop(&mut self, mut piece: Piece) -> isize {
-        if !self.drop(&mut piece) {
-            *self = Board::default();
-        }
-        self.place_unchecked(piece)
+    if result.combo > 1 {
+        callout = format!("{}\n{} Combo", callout, result.combo - 1);
     }
-    fn _place_checked(&mut self, piece: Piece) -> bool {
-        if self.check_collision(piece) {
-            self.place_unchecked(piece);
-            true
-        } else {
-            false
-        }
-    }
-    fn place_unchecked(&mut self, piece: Piece) -> isize {
-        for (x, y) in piece.filled() {
-            self.board[x as usize][y as usize].filled = Some(piece.kind)
-        }
-        self.clear_lines()
-    }
-    fn clear_lines(&mut self) -> isize {
-        let mut counter = 0;
-        self.board = array_init::from_iter(
-            self.board
-                .iter()
-                .filter(|row| {
-                    if row.iter().any(|cell| cell.filled.is_none()) {
-                        true
-                    } else {
-                        counter += 1;
-                        false
-                    }
-                })
-                .copied()
-                .chain([[Cell::default(); 10]; 1].iter().cycle().copied()),
-        )
-        .unwrap();
-        counter
-    }
-    fn draw_board_ggez(&self, ctx: &mut Context, x: f32, y: f32) -> GameResult {
-        for (ypos, row) in self.board.iter().enumerate() {
-            for (xpos, cell) in row.iter().enumerate() {
-                if cell.filled.is_some() {
-                    let rectangle = graphics::Mesh::new_rectangle(
-                        ctx,
-                        graphics::DrawMode::fill(),
-                        Rect::new(
-                            x + 1.0 + 16.0 * xpos as f32,
-                            y + 1.0 - 16.0 * (ypos + 1) as f32,
-                            14.0,
-                            14.0,
-                        ),
-                        cell.filled.unwrap().color(),
-                    )?;
-                    graphics::draw(ctx, &rectangle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
-                }
-            }
-        }
-        Ok(())
-    }
-}
-
-fn generate_batch(rng: &mut ThreadRng, batch: &mut Vec<Tetromino>) {
-    *batch = (0..7).map(|x| Tetromino::from_i8(x).unwrap()).collect();
-    batch.shuffle(rng);
+    Some(callout)
 }
 
-#[derive(Default, Debug)]
-struct Tetris {
-    next_tick: Option<Instant>,
-    tick_speed: Duration,
-    soft_drop_speed: Duration,
-    board: Board,
-    rng: ThreadRng,
-    current_batch: Vec<Tetromino>,
-    next_batch: Vec<Tetromino>,
-    current_piece: Option<Piece>,
-    das_time: Option<(Instant, bool)>,
-    hold: (bool, Option<Tetromino>),
-    soft_dropping: bool,
-    lines_remaining: Option<isize>,
-    start_time: Option<Instant>,
-    final_time: Option<Duration>,
-    halted: bool,
+fn key_action(keycode: KeyCode) -> Option<GameAction> {
+    match keycode {
+        KeyCode::Up => Some(GameAction::HardDrop),
+        KeyCode::Down => Some(GameAction::SoftDrop),
+        KeyCode::Left => Some(GameAction::MoveLeft),
+        KeyCode::Right => Some(GameAction::MoveRight),
+        KeyCode::C => Some(GameAction::Hold),
+        KeyCode::X => Some(GameAction::RotateCW),
+        KeyCode::Z => Some(GameAction::RotateCCW),
+        KeyCode::A => Some(GameAction::Rotate180),
+        KeyCode::Escape => Some(GameAction::Pause),
+        KeyCode::R => Some(GameAction::Restart),
+        _ => None,
+    }
 }
 
-impl Tetris {
-    fn next_piece(&mut self) -> Tetromino {
-        if self.current_batch.is_empty() {
-            swap(&mut self.current_batch, &mut self.next_batch);
-            generate_batch(&mut self.rng, &mut self.next_batch);
-            if self.current_batch.is_empty() {
-                generate_batch(&mut self.rng, &mut self.current_batch)
-            }
-        }
-        self.current_batch.pop().unwrap()
-    }
-    fn _place_random(&mut self) {
-        let kind = self.next_piece();
-        let rotation = self.rng.gen_range(0, 4);
-        let column = self.rng.gen_range(0, 11 - kind.width(rotation) as u8);
-        let piece = Piece {
-            kind,
-            column,
-            row: 20,
-            rotation,
-        };
-        self.board.hard_drop(piece);
-    }
-    fn switch_hold(&mut self) {
-        if !self.hold.0 {
-            if let Some(mut current) = self.current_piece {
-                if let (_, Some(kind)) = &mut self.hold {
-                    swap(&mut current.kind, kind);
-                    current.column = 3;
-                    current.row = 18;
-                } else {
-                    self.hold.1 = Some(current.kind);
-                    current = Piece {
-                        kind: self.next_piece(),
-                        column: 3,
-                        row: 18,
-                        rotation: 0,
-                    };
-                }
-                self.hold.0 = true;
-                self.current_piece = Some(current);
-            }
-        }
-    }
+struct Frontend {
+    game: Tetris,
 }
 
-impl EventHandler for Tetris {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
-        if self.halted {
-            return Ok(());
-        }
-        if let Some(lines) = self.lines_remaining {
-            if lines <= 0 {
-                self.board = Board::default();
-                if let Some(start) = self.start_time {
-                    self.final_time = Some(Instant::now() - start);
-                }
-                self.lines_remaining = Some(40);
-                self.halted = true;
-            }
-        }
-        if self.current_piece.is_none() {
-            self.current_piece = Some(Piece {
-                kind: self.next_piece(),
-                column: 3,
-                row: 18,
-                rotation: 0,
-            });
-        }
-        if let Some(mut time) = self.next_tick {
-            if let Some(mut piece) = self.current_piece {
-                if let Some((das_time, right)) = self.das_time {
-                    if das_time < Instant::now() {
-                        if right {
-                            self.board.das_right(&mut piece)
-                        } else {
-                            self.board.das_left(&mut piece)
-                        }
-                        self.das_time = None;
-                    }
-                }
-                self.current_piece = Some(piece);
-                while Instant::now() > time {
-                    if let Some(cleared) = self.board.move_piece_down(&mut piece) {
-                        if let Some(remaining) = &mut self.lines_remaining {
-                            *remaining -= cleared;
-                        }
-                        self.current_piece = None;
-                    } else {
-                        self.current_piece = Some(piece);
-                        self.tick_speed *= 499;
-                        self.tick_speed /= 500;
-                    }
-                    time += if self.soft_dropping {
-                        self.soft_drop_speed
-                    } else {
-                        self.tick_speed
-                    };
-                }
-                self.next_tick = Some(time);
-            }
+impl EventHandler for Frontend {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        while timer::check_update_time(ctx, TICKS_PER_SECOND) {
+            self.game.update();
         }
         Ok(())
     }
@@ -468,15 +198,24 @@ impl EventHandler for Tetris {
         graphics::clear(ctx, graphics::BLACK);
 
         let (width, height): (f64, f64) = window(&ctx).get_inner_size().unwrap().into();
+        let (width, height) = (width as f32, height as f32);
+
+        let board = self.game.board();
+        let system = board.rotation_system();
+        let visible_height = board.visible_height();
+        let field_width = 16.0 * board.width() as f32;
+        let field_height = 16.0 * visible_height as f32;
+        let left = width / 2.0 - field_width / 2.0;
+        let origin = (left, height);
 
         let outer = graphics::Mesh::new_rectangle(
             ctx,
             graphics::DrawMode::fill(),
             Rect::new(
-                width as f32 / 2.0 - 89.0,
-                height as f32 - 329.0,
-                178.0,
-                329.0,
+                left - 9.0,
+                height - field_height - 9.0 - PEEK_HEIGHT,
+                field_width + 18.0,
+                field_height + 9.0 + PEEK_HEIGHT,
             ),
             (63, 191, 191).into(),
         )?;
@@ -485,10 +224,10 @@ impl EventHandler for Tetris {
             ctx,
             graphics::DrawMode::fill(),
             Rect::new(
-                width as f32 / 2.0 - 81.0,
-                height as f32 - 321.0,
-                162.0,
-                321.0,
+                left - 1.0,
+                height - field_height - 1.0 - PEEK_HEIGHT,
+                field_width + 2.0,
+                field_height + 1.0 + PEEK_HEIGHT,
             ),
             (0, 0, 0).into(),
         )?;
@@ -496,74 +235,140 @@ impl EventHandler for Tetris {
         graphics::draw(ctx, &outer, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
         graphics::draw(ctx, &inner, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
 
-        self.board
-            .draw_board_ggez(ctx, width as f32 / 2.0 - 80.0, height as f32)?;
-        if let Some(piece) = self.current_piece {
-            piece.draw_ggez(ctx, width as f32 / 2.0 - 80.0, height as f32)?;
-            let mut ghost = piece;
-            self.board.drop(&mut ghost);
-            ghost.draw_ghost_ggez(ctx, width as f32 / 2.0 - 80.0, height as f32)?;
+        draw_board_ggez(ctx, board, origin)?;
+        if self.game.is_clearing_lines() {
+            for row in board.full_rows() {
+                for column in 0..board.width() {
+                    draw_cell(ctx, (row, column), origin, visible_height, graphics::WHITE)?;
+                }
+            }
+        }
+        if let Some(piece) = self.game.current_piece() {
+            let mut color: Color = system.color(piece.kind).into();
+            let brightness = 1.0 - 0.6 * self.game.lock_progress();
+            color.r *= brightness;
+            color.g *= brightness;
+            color.b *= brightness;
+            draw_piece(ctx, system, piece, origin, visible_height, color)?;
+        }
+        if let Some(ghost) = self.game.ghost_piece() {
+            draw_ghost_ggez(ctx, system, ghost, origin, visible_height)?;
         }
 
-        if let (_, Some(kind)) = self.hold {
-            Piece {
-                kind,
-                column: 0,
-                row: 18,
-                rotation: 0,
-            }
-            .draw_ggez(ctx, width as f32 / 2.0 - 160.0, height as f32)?;
+        let top_row = visible_height as i32 - 2;
+        if let Some(kind) = self.game.hold_piece() {
+            let rotation = system.spawn_rotation(kind);
+            draw_piece_ggez(
+                ctx,
+                system,
+                Piece {
+                    kind,
+                    column: 0,
+                    row: top_row - system.bottom(kind, rotation),
+                    rotation,
+                },
+                (left - 80.0, height),
+                visible_height,
+            )?;
         }
 
-        self.current_batch
-            .iter()
-            .rev()
-            .chain(self.next_batch.iter().rev())
+        self.game
+            .queue()
             .take(5)
             .enumerate()
-            .map(|(index, &kind)| Piece {
-                kind,
-                column: 12,
-                row: 18 - 3 * index as u8,
-                rotation: 0,
+            .map(|(index, kind)| {
+                let rotation = system.spawn_rotation(kind);
+                Piece {
+                    kind,
+                    column: board.width() as i32 + 2,
+                    row: top_row - 3 * index as i32 - system.bottom(kind, rotation),
+                    rotation,
+                }
             })
-            .map(|piece| piece.draw_ggez(ctx, width as f32 / 2.0 - 80.0, height as f32))
+            .map(|piece| draw_piece_ggez(ctx, system, piece, origin, visible_height))
             .collect::<Result<Vec<()>, _>>()?;
 
-        if let Some(lines) = self.lines_remaining {
+        if let Some(lines) = self.game.lines_remaining() {
             let lines = graphics::Text::new(lines.to_string());
             graphics::draw(
                 ctx,
                 &lines,
                 DrawParam::new()
                     .dest(ggez::mint::Point2 {
-                        x: width as f32 / 2.0 - 160.0,
-                        y: (height - 360.0) as f32,
+                        x: left - 80.0,
+                        y: height - field_height - 40.0,
                     })
                     .scale([2.0, 2.0]),
             )?;
         }
 
-        if let Some(delta) = if let Some(time) = self.final_time {
-            Some(time)
-        } else if let Some(start) = self.start_time {
-            Some(Instant::now() - start)
-        } else {
-            None
-        } {
-            let time = graphics::Text::new(format!(
-                "{}:{:0>2}.{:0>3}",
-                delta.as_secs() / 60,
-                delta.as_secs() % 60,
-                delta.subsec_millis()
-            ));
+        let level = graphics::Text::new(format!("Level {}", self.game.level()));
+        graphics::draw(
+            ctx,
+            &level,
+            DrawParam::new().dest(ggez::mint::Point2 {
+                x: left - 160.0,
+                y: height - field_height / 2.0 + 20.0,
+            }),
+        )?;
+
+        let score = graphics::Text::new(format!(
+            "Score {}  Attack {}",
+            self.game.score(),
+            self.game.attack()
+        ));
+        graphics::draw(
+            ctx,
+            &score,
+            DrawParam::new().dest(ggez::mint::Point2 {
+                x: left - 160.0,
+                y: height - field_height / 2.0 + 40.0,
+            }),
+        )?;
+
+        if let Some(callout) = self.game.last_lock().and_then(lock_callout) {
+            let callout = graphics::Text::new(callout);
             graphics::draw(
                 ctx,
-                &time,
+                &callout,
+                DrawParam::new().dest(ggez::mint::Point2 {
+                    x: left - 160.0,
+                    y: height - field_height / 2.0 + 60.0,
+                }),
+            )?;
+        }
+
+        let time = graphics::Text::new(format_time(self.game.elapsed()));
+        graphics::draw(
+            ctx,
+            &time,
+            DrawParam::new()
+                .dest(ggez::mint::Point2 {
+                    x: left - 160.0,
+                    y: height - field_height / 2.0 - 40.0,
+                })
+                .scale([2.0, 2.0]),
+        )?;
+
+        let seed = graphics::Text::new(format!("Seed: {}", self.game.seed()));
+        graphics::draw(
+            ctx,
+            &seed,
+            DrawParam::new().dest(ggez::mint::Point2 {
+                x: left - 160.0,
+                y: height - field_height / 2.0,
+            }),
+        )?;
+
+        if let Some(banner) = state_banner(&self.game) {
+            let banner = graphics::Text::new(banner);
+            graphics::draw(
+                ctx,
+                &banner,
                 DrawParam::new()
                     .dest(ggez::mint::Point2 {
-                        x: width as f32 / 2.0 - 240.0,
-                        y: (height - 200.0) as f32,
+                        x: left + 8.0,
+                        y: height - field_height / 2.0 - 40.0,
                     })
                     .scale([2.0, 2.0]),
             )?;
@@ -581,92 +386,153 @@ impl EventHandler for Tetris {
         if repeat {
             return;
         }
-        match keycode {
-            KeyCode::Up => {
-                if let Some(piece) = self.current_piece {
-                    let cleared = self.board.hard_drop(piece);
-                    self.lines_remaining = self.lines_remaining.map(|x| x - cleared);
-                    self.hold.0 = false;
-                }
-                self.current_piece = None;
-            }
-            KeyCode::Down => {
-                self.board.move_piece_down(&mut self.current_piece.unwrap());
-                self.soft_dropping = true;
-                self.next_tick = min(Some(Instant::now() + self.soft_drop_speed), self.next_tick);
-            }
-            KeyCode::Left => {
-                if let Some(mut piece) = self.current_piece {
-                    self.board.move_piece_left(&mut piece);
-                    self.das_time = Some((Instant::now() + Duration::from_millis(50), false));
-                    self.current_piece = Some(piece);
-                }
-            }
-            KeyCode::Right => {
-                if let Some(mut piece) = self.current_piece {
-                    self.board.move_piece_right(&mut piece);
-                    self.das_time = Some((Instant::now() + Duration::from_millis(50), true));
-                    self.current_piece = Some(piece);
-                }
-            }
-            KeyCode::C => {
-                self.switch_hold();
-            }
-            KeyCode::X => {
-                if let Some(mut piece) = self.current_piece {
-                    self.board.rotate_piece_clockwise(&mut piece);
-                    self.current_piece = Some(piece);
-                }
-            }
-            KeyCode::Z => {
-                if let Some(mut piece) = self.current_piece {
-                    self.board.rotate_piece_counterclockwise(&mut piece);
-                    self.current_piece = Some(piece);
-                }
-            }
-            _ => {}
-        };
+        if let Some(action) = key_action(keycode) {
+            self.game.handle_input(InputEvent::Pressed(action));
+        }
     }
     fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymods: KeyMods) {
-        match keycode {
-            KeyCode::Down => {
-                self.soft_dropping = false;
+        if let Some(action) = key_action(keycode) {
+            self.game.handle_input(InputEvent::Released(action));
+        }
+    }
+}
+
+/// Parses a duration given in milliseconds into the nearest whole number of
+/// ticks, or `None` if it is not a number or too long to count in ticks.
+fn millis_to_ticks(value: &str) -> Option<u32> {
+    let millis = value.parse::<u32>().ok()?;
+    let scaled = millis.checked_mul(TICKS_PER_SECOND)?.checked_add(500)?;
+    Some(scaled / 1000)
+}
+
+fn parse_soft_drop(value: &str) -> Option<SoftDrop> {
+    if value == "inf" {
+        Some(SoftDrop::Infinite)
+    } else if let Some(factor) = value.strip_suffix('x') {
+        factor.parse().ok().map(SoftDrop::Factor)
+    } else if let Some(rate) = value.strip_suffix('G') {
+        rate.parse().ok().map(SoftDrop::Rate)
+    } else {
+        None
+    }
+}
+
+fn parse_gravity(value: &str) -> Option<GravityCurve> {
+    match value {
+        "guideline" => Some(GravityCurve::Guideline),
+        "nes" => Some(GravityCurve::Nes),
+        "tgm" => Some(GravityCurve::Tgm),
+        _ => value
+            .strip_suffix('G')
+            .and_then(|gravity| gravity.parse().ok())
+            .map(GravityCurve::Fixed),
+    }
+}
+
+fn parse_args() -> (Option<u64>, Config) {
+    let mut seed = None;
+    let mut config = Config::default();
+    let mut args = std::env::args().skip(1);
+    while let (Some(flag), Some(value)) = (args.next(), args.next()) {
+        match flag.as_str() {
+            "--seed" => seed = value.parse().ok(),
+            "--width" => config.width = value.parse().unwrap_or(config.width).max(4),
+            "--height" => config.height = value.parse().unwrap_or(config.height).max(1),
+            "--buffer" => config.buffer_height = value.parse().unwrap_or(config.buffer_height),
+            "--rotation" => match value.as_str() {
+                "srs" => config.rotation_system = Arc::new(Srs),
+                "ars" => config.rotation_system = Arc::new(Ars),
+                "nrs" => config.rotation_system = Arc::new(Nrs),
+                _ => eprintln!("Unknown rotation system: {}", value),
+            },
+            "--pieces" => match PieceSet::load(&value) {
+                Ok(pieces) => config.rotation_system = Arc::new(pieces),
+                Err(error) => eprintln!("{}: {}", value, error),
+            },
+            "--all-spins" => config.all_spins = value.parse().unwrap_or(config.all_spins),
+            "--das" => config.das = millis_to_ticks(&value).unwrap_or(config.das),
+            "--arr" => config.arr = millis_to_ticks(&value).unwrap_or(config.arr),
+            "--das-cut" => config.das_cut = millis_to_ticks(&value).unwrap_or(config.das_cut),
+            "--das-priority" => match value.as_str() {
+                "last" => config.das_priority = DasPriority::LastPressed,
+                "cancel" => config.das_priority = DasPriority::Cancel,
+                "first" => config.das_priority = DasPriority::FirstPressed,
+                _ => eprintln!("Unknown DAS priority: {}", value),
+            },
+            "--gravity" => match parse_gravity(&value) {
+                Some(gravity) => config.gravity = gravity,
+                None => eprintln!("Unknown gravity: {}", value),
+            },
+            "--level" => config.start_level = value.parse().unwrap_or(config.start_level),
+            "--level-up" => match value.as_str() {
+                "tgm" => config.level_progression = LevelProgression::Tgm,
+                lines => match lines.parse() {
+                    Ok(lines) => config.level_progression = LevelProgression::Lines(lines),
+                    Err(_) => eprintln!("Unknown level progression: {}", value),
+                },
+            },
+            "--irs" => config.initial_rotation = value.parse().unwrap_or(config.initial_rotation),
+            "--ihs" => config.initial_hold = value.parse().unwrap_or(config.initial_hold),
+            "--soft-drop" => match parse_soft_drop(&value) {
+                Some(soft_drop) => config.soft_drop = soft_drop,
+                None => eprintln!("Unknown soft drop speed: {}", value),
+            },
+            "--are" => config.entry_delay = millis_to_ticks(&value).unwrap_or(config.entry_delay),
+            "--line-clear-delay" => {
+                config.line_clear_delay = millis_to_ticks(&value).unwrap_or(config.line_clear_delay)
             }
-            KeyCode::Left => {
-                if !if let Some((_, right)) = self.das_time {
-                    right
-                } else {
-                    true
-                } {
-                    self.das_time = None;
-                }
+            "--lock-delay" => {
+                config.lock_delay = millis_to_ticks(&value).unwrap_or(config.lock_delay)
             }
-            KeyCode::Right => {
-                if if let Some((_, right)) = self.das_time {
-                    right
-                } else {
-                    false
-                } {
-                    self.das_time = None;
-                }
+            "--lock-reset" => match value.as_str() {
+                "step" => config.lock_reset = LockReset::Step,
+                "move" => config.lock_reset = LockReset::Move,
+                "infinite" => config.lock_reset = LockReset::Infinite,
+                _ => eprintln!("Unknown lock reset: {}", value),
+            },
+            "--max-resets" => {
+                config.max_lock_resets = value.parse().unwrap_or(config.max_lock_resets)
             }
-            _ => {}
+            "--attack" => match value.as_str() {
+                "guideline" => config.attack = AttackTable::guideline(),
+                "tetrio" => config.attack = AttackTable::tetrio(),
+                _ => eprintln!("Unknown attack table: {}", value),
+            },
+            "--kicks-180" => match value.as_str() {
+                "system" => config.kicks_180 = Kicks180::System,
+                "none" => config.kicks_180 = Kicks180::None,
+                "srs+" => config.kicks_180 = Kicks180::SrsPlus,
+                _ => eprintln!("Unknown 180 kick table: {}", value),
+            },
+            _ => eprintln!("Unknown option: {}", flag),
         }
     }
+    (seed, config)
 }
 
 fn main() {
-    let (mut ctx, mut event_loop) = ContextBuilder::new("Tetris", "ix").build().unwrap();
-    let mut test = Tetris::default();
-    let rng = rand::thread_rng();
-    test.rng = rng;
-    test.tick_speed = Duration::from_millis(1000);
-    test.soft_drop_speed = Duration::from_millis(20);
-    test.next_tick = Some(Instant::now() + Duration::from_millis(1000));
-    test.lines_remaining = Some(40);
-    test.start_time = Some(Instant::now());
-    match event::run(&mut ctx, &mut event_loop, &mut test) {
+    let (seed, config) = parse_args();
+    let window_mode = WindowMode::default().dimensions(
+        (16.0 * config.width as f32 + 400.0).max(800.0),
+        (16.0 * config.height as f32 + 60.0).max(600.0),
+    );
+    let (mut ctx, mut event_loop) = ContextBuilder::new("Tetris", "ix")
+        .window_mode(window_mode)
+        .build()
+        .unwrap();
+    let game = match seed {
+        Some(seed) => Tetris::with_config(seed, config),
+        None => Tetris::with_random_seed(config),
+    };
+    println!("Seed: {}", game.seed());
+    let mut frontend = Frontend { game };
+    match event::run(&mut ctx, &mut event_loop, &mut frontend) {
         Ok(_) => println!("Exited cleanly."),
         Err(e) => println!("Error occured: {}", e),
     }
+    println!("Seed: {}", frontend.game.seed());
+    println!("Time: {}", format_time(frontend.game.elapsed()));
+    println!("Score: {}", frontend.game.score());
+    println!("Perfect clears: {}", frontend.game.perfect_clears());
+    println!("Attack: {}", frontend.game.attack());
 }
@@ -0,0 +1,101 @@
+use crate::filled::SRS_PLUS_180_KICKS;
+use crate::rotation::RotationSystem;
+
+/// A piece placed somewhere on (or above) the board.
+///
+/// `row` and `column` locate the bottom-left corner of the piece's bounding box,
+/// with row 0 being the bottom of the board. The bounding box may extend past
+/// the edges of the board as long as the piece's cells do not.
+#[derive(Debug, Clone, Copy)]
+pub struct Piece {
+    pub kind: PieceKind,
+    pub column: i32,
+    pub row: i32,
+    pub rotation: u8,
+}
+
+/// Iterator over the `(row, column)` board coordinates covered by a [`Piece`].
+#[derive(Debug, Clone, Copy)]
+pub struct PieceBlockIter<'a> {
+    block_kind: &'a [(u8, u8)],
+    column: i32,
+    row: i32,
+}
+
+impl<'a> Iterator for PieceBlockIter<'a> {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<(i32, i32)> {
+        let ((x, y), remaining) = self.block_kind.split_first()?;
+        self.block_kind = remaining;
+        Some((*x as i32 + self.row, *y as i32 + self.column))
+    }
+}
+
+impl Piece {
+    /// Returns the board cells covered by this piece as `(row, column)` pairs,
+    /// with its shape taken from `system`.
+    pub fn filled<'a>(&self, system: &'a dyn RotationSystem) -> PieceBlockIter<'a> {
+        PieceBlockIter {
+            block_kind: system.cells(self.kind, self.rotation),
+            column: self.column,
+            row: self.row,
+        }
+    }
+}
+
+/// Identifies a kind of piece within a [`RotationSystem`]'s piece set.
+///
+/// The built-in rotation systems use the seven standard tetrominoes, which have
+/// named constants here. Piece sets loaded from a file number their pieces in
+/// the order they are defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PieceKind(pub u8);
+
+impl PieceKind {
+    pub const O: PieceKind = PieceKind(0);
+    pub const T: PieceKind = PieceKind(1);
+    pub const L: PieceKind = PieceKind(2);
+    pub const J: PieceKind = PieceKind(3);
+    pub const S: PieceKind = PieceKind(4);
+    pub const Z: PieceKind = PieceKind(5);
+    pub const I: PieceKind = PieceKind(6);
+    /// The seven standard tetrominoes.
+    pub const TETROMINOES: [PieceKind; 7] = [
+        Self::O,
+        Self::T,
+        Self::L,
+        Self::J,
+        Self::S,
+        Self::Z,
+        Self::I,
+    ];
+    /// Position of this kind in its piece set.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Wall kicks to try when rotating a piece by 180 degrees.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Kicks180 {
+    /// Whatever the rotation system defines.
+    #[default]
+    System,
+    /// Only rotate in place.
+    None,
+    /// The SRS+ table used by Tetr.io, for every piece.
+    SrsPlus,
+}
+
+impl Kicks180 {
+    /// Offsets to try, as `(column, row)` pairs, when rotating `kind` out of
+    /// `rotation` under `system`.
+    pub fn kicks(self, system: &dyn RotationSystem, kind: PieceKind, rotation: u8) -> &[(i8, i8)] {
+        match self {
+            Self::System => system.kicks_180(kind, rotation),
+            Self::None => &[(0, 0)],
+            Self::SrsPlus => &SRS_PLUS_180_KICKS[rotation as usize % 4],
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use crate::piece::PieceKind;
+use crate::rotation::RotationSystem;
+use serde::Deserialize;
+use std::convert::TryInto;
+use std::error::Error;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+/// A piece set described by data rather than code, for playing with trominoes,
+/// pentominoes or any other polyominoes.
+///
+/// Piece sets are usually loaded from a TOML file with [`PieceSet::load`]. Each
+/// `[[piece]]` entry gives the piece's name, color and shape, and optionally its
+/// spawn rotation and wall kicks:
+///
+/// ```toml
+/// # Kicks used by pieces that do not define their own. Each list holds the
+/// # (column, row) offsets to try when turning out of rotation 0, 1, 2 and 3.
+/// # The 180 degree kicks are optional.
+/// [kicks]
+/// cw = [[[0, 0], [-1, 0], [1, 0]], [[0, 0], [1, 0], [-1, 0]], [[0, 0], [1, 0], [-1, 0]], [[0, 0], [-1, 0], [1, 0]]]
+/// ccw = [[[0, 0], [1, 0], [-1, 0]], [[0, 0], [1, 0], [-1, 0]], [[0, 0], [-1, 0], [1, 0]], [[0, 0], [-1, 0], [1, 0]]]
+/// 180 = [[[0, 0], [0, 1]], [[0, 0], [1, 0]], [[0, 0], [0, -1]], [[0, 0], [-1, 0]]]
+///
+/// [[piece]]
+/// name = "V"
+/// color = [255, 127, 0]
+/// spawn_rotation = 0
+/// rotations = [["V..", "V..", "VVV"]]
+/// ```
+///
+/// A top level `t_piece = "<name>"` names the piece that can T-spin; by default
+/// none can.
+///
+/// Shapes are drawn top row first, with `.` or a space marking empty cells, and
+/// cover the whole square bounding box the piece turns within. A piece may give
+/// all four rotations, or only the first and have the others made by turning
+/// its bounding box. Pieces without kicks, whether their own or the set's, only
+/// rotate in place, and the same goes for 180 degree turns without 180 kicks.
+#[derive(Debug, Clone)]
+pub struct PieceSet {
+    pieces: Vec<PieceDef>,
+    t_piece: Option<PieceKind>,
+}
+
+/// A single piece of a [`PieceSet`].
+#[derive(Debug, Clone)]
+pub struct PieceDef {
+    /// Short display name.
+    pub name: String,
+    /// Display color as an `(r, g, b)` triple.
+    pub color: (u8, u8, u8),
+    /// Cells of each rotation, as `(row, column)` offsets from the bottom-left
+    /// corner of the bounding box.
+    pub rotations: [Vec<(u8, u8)>; 4],
+    /// Rotation the piece enters the board in.
+    pub spawn_rotation: u8,
+    /// Offsets to try, as `(column, row)` pairs, when turning clockwise out of
+    /// each rotation.
+    pub kicks_clockwise: [Vec<(i8, i8)>; 4],
+    /// Offsets to try, as `(column, row)` pairs, when turning counterclockwise
+    /// out of each rotation.
+    pub kicks_counterclockwise: [Vec<(i8, i8)>; 4],
+    /// Offsets to try, as `(column, row)` pairs, when turning 180 degrees out of
+    /// each rotation.
+    pub kicks_180: [Vec<(i8, i8)>; 4],
+}
+
+/// Why a [`PieceSet`] could not be loaded.
+#[derive(Debug)]
+pub enum PieceSetError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not a valid piece set description.
+    Parse(toml::de::Error),
+    /// The description parsed but does not make a playable piece set.
+    Invalid(String),
+}
+
+impl fmt::Display for PieceSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PieceSetError::Io(error) => write!(f, "could not read piece set: {}", error),
+            PieceSetError::Parse(error) => write!(f, "could not parse piece set: {}", error),
+            PieceSetError::Invalid(reason) => write!(f, "invalid piece set: {}", reason),
+        }
+    }
+}
+
+impl Error for PieceSetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PieceSetError::Io(error) => Some(error),
+            PieceSetError::Parse(error) => Some(error),
+            PieceSetError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for PieceSetError {
+    fn from(error: io::Error) -> Self {
+        PieceSetError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for PieceSetError {
+    fn from(error: toml::de::Error) -> Self {
+        PieceSetError::Parse(error)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPieceSet {
+    t_piece: Option<String>,
+    kicks: Option<RawKicks>,
+    piece: Vec<RawPiece>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPiece {
+    name: String,
+    color: (u8, u8, u8),
+    rotations: Vec<Vec<String>>,
+    #[serde(default)]
+    spawn_rotation: u8,
+    kicks: Option<RawKicks>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct RawKicks {
+    cw: Vec<Vec<(i8, i8)>>,
+    ccw: Vec<Vec<(i8, i8)>>,
+    #[serde(rename = "180")]
+    half_turn: Option<Vec<Vec<(i8, i8)>>>,
+}
+
+fn parse_shape(rows: &[String]) -> (Vec<(u8, u8)>, usize) {
+    let mut cells = Vec::new();
+    let mut size = rows.len();
+    for (index, line) in rows.iter().enumerate() {
+        let row = rows.len() - 1 - index;
+        for (column, ch) in line.chars().enumerate() {
+            size = size.max(column + 1);
+            if ch != '.' && !ch.is_whitespace() {
+                cells.push((row as u8, column as u8));
+            }
+        }
+    }
+    (cells, size)
+}
+
+fn turn_clockwise(cells: &[(u8, u8)], size: usize) -> Vec<(u8, u8)> {
+    cells
+        .iter()
+        .map(|&(row, column)| (size as u8 - 1 - column, row))
+        .collect()
+}
+
+fn kick_table(lists: Vec<Vec<(i8, i8)>>, name: &str) -> Result<[Vec<(i8, i8)>; 4], PieceSetError> {
+    lists.try_into().map_err(|_| {
+        PieceSetError::Invalid(format!("kicks of {} must have 4 lists per direction", name))
+    })
+}
+
+impl PieceSet {
+    /// Builds a piece set from already parsed definitions, checking that every
+    /// piece can be played. `t_piece` is the piece that can T-spin, if any.
+    pub fn new(pieces: Vec<PieceDef>, t_piece: Option<PieceKind>) -> Result<Self, PieceSetError> {
+        if pieces.is_empty() {
+            return Err(PieceSetError::Invalid("no pieces defined".to_string()));
+        }
+        if pieces.len() > u8::MAX as usize + 1 {
+            return Err(PieceSetError::Invalid("too many pieces".to_string()));
+        }
+        for piece in &pieces {
+            if piece.rotations.iter().any(Vec::is_empty) {
+                return Err(PieceSetError::Invalid(format!(
+                    "{} has a rotation with no cells",
+                    piece.name
+                )));
+            }
+            if piece.spawn_rotation >= 4 {
+                return Err(PieceSetError::Invalid(format!(
+                    "{} has spawn rotation {}, which is not between 0 and 3",
+                    piece.name, piece.spawn_rotation
+                )));
+            }
+        }
+        if t_piece.is_some_and(|kind| kind.index() >= pieces.len()) {
+            return Err(PieceSetError::Invalid(
+                "T piece is not in the set".to_string(),
+            ));
+        }
+        Ok(PieceSet { pieces, t_piece })
+    }
+    /// Parses a piece set from the TOML description documented on [`PieceSet`].
+    pub fn from_toml(source: &str) -> Result<Self, PieceSetError> {
+        let RawPieceSet {
+            t_piece,
+            kicks,
+            piece,
+        } = toml::from_str(source)?;
+        let mut pieces = Vec::with_capacity(piece.len());
+        for piece in piece {
+            let (first, size) = match piece.rotations.first() {
+                Some(rows) => parse_shape(rows),
+                None => {
+                    return Err(PieceSetError::Invalid(format!(
+                        "{} has no rotations",
+                        piece.name
+                    )))
+                }
+            };
+            let rotations = match piece.rotations.len() {
+                1 => {
+                    let second = turn_clockwise(&first, size);
+                    let third = turn_clockwise(&second, size);
+                    let fourth = turn_clockwise(&third, size);
+                    [first, second, third, fourth]
+                }
+                4 => [
+                    first,
+                    parse_shape(&piece.rotations[1]).0,
+                    parse_shape(&piece.rotations[2]).0,
+                    parse_shape(&piece.rotations[3]).0,
+                ],
+                _ => {
+                    return Err(PieceSetError::Invalid(format!(
+                        "{} must have either 1 or 4 rotations",
+                        piece.name
+                    )))
+                }
+            };
+            let in_place = [vec![(0, 0)], vec![(0, 0)], vec![(0, 0)], vec![(0, 0)]];
+            let (kicks_clockwise, kicks_counterclockwise, kicks_180) =
+                match piece.kicks.or_else(|| kicks.clone()) {
+                    Some(kicks) => (
+                        kick_table(kicks.cw, &piece.name)?,
+                        kick_table(kicks.ccw, &piece.name)?,
+                        match kicks.half_turn {
+                            Some(half_turn) => kick_table(half_turn, &piece.name)?,
+                            None => in_place,
+                        },
+                    ),
+                    None => (in_place.clone(), in_place.clone(), in_place),
+                };
+            pieces.push(PieceDef {
+                name: piece.name,
+                color: piece.color,
+                rotations,
+                spawn_rotation: piece.spawn_rotation,
+                kicks_clockwise,
+                kicks_counterclockwise,
+                kicks_180,
+            });
+        }
+        let t_piece = match t_piece {
+            Some(name) => match pieces.iter().position(|piece| piece.name == name) {
+                Some(index) => Some(PieceKind(index as u8)),
+                None => {
+                    return Err(PieceSetError::Invalid(format!(
+                        "T piece {} is not defined",
+                        name
+                    )))
+                }
+            },
+            None => None,
+        };
+        PieceSet::new(pieces, t_piece)
+    }
+    /// Reads and parses a piece set file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PieceSetError> {
+        PieceSet::from_toml(&fs::read_to_string(path)?)
+    }
+    /// The pieces in the set, in the order they are numbered.
+    pub fn pieces(&self) -> &[PieceDef] {
+        &self.pieces
+    }
+}
+
+impl RotationSystem for PieceSet {
+    fn piece_count(&self) -> usize {
+        self.pieces.len()
+    }
+    fn name(&self, kind: PieceKind) -> &str {
+        &self.pieces[kind.index()].name
+    }
+    fn color(&self, kind: PieceKind) -> (u8, u8, u8) {
+        self.pieces[kind.index()].color
+    }
+    fn cells(&self, kind: PieceKind, rotation: u8) -> &[(u8, u8)] {
+        &self.pieces[kind.index()].rotations[rotation as usize % 4]
+    }
+    fn spawn_rotation(&self, kind: PieceKind) -> u8 {
+        self.pieces[kind.index()].spawn_rotation
+    }
+    fn t_piece(&self) -> Option<PieceKind> {
+        self.t_piece
+    }
+    fn kicks(&self, kind: PieceKind, from: u8, to: u8) -> &[(i8, i8)] {
+        let piece = &self.pieces[kind.index()];
+        if to == (from + 1) % 4 {
+            &piece.kicks_clockwise[from as usize % 4]
+        } else {
+            &piece.kicks_counterclockwise[from as usize % 4]
+        }
+    }
+    fn kicks_180(&self, kind: PieceKind, from: u8) -> &[(i8, i8)] {
+        &self.pieces[kind.index()].kicks_180[from as usize % 4]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V: &str = r#"
+        [[piece]]
+        name = "V"
+        color = [255, 127, 0]
+        rotations = [["V..", "V..", "VVV"]]
+    "#;
+
+    fn invalid(source: &str) -> String {
+        match PieceSet::from_toml(source) {
+            Err(PieceSetError::Invalid(reason)) => reason,
+            other => panic!("expected an invalid piece set, got {:?}", other),
+        }
+    }
+
+    fn sorted(cells: &[(u8, u8)]) -> Vec<(u8, u8)> {
+        let mut cells = cells.to_vec();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn one_rotation_turns_into_four() {
+        let set = PieceSet::from_toml(V).unwrap();
+        let v = &set.pieces()[0];
+        assert_eq!(
+            sorted(&v.rotations[0]),
+            [(0, 0), (0, 1), (0, 2), (1, 0), (2, 0)]
+        );
+        assert_eq!(
+            sorted(&v.rotations[1]),
+            [(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]
+        );
+        assert_eq!(
+            sorted(&v.rotations[2]),
+            [(0, 2), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
+        assert_eq!(
+            sorted(&v.rotations[3]),
+            [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)]
+        );
+        assert_eq!(v.spawn_rotation, 0);
+        assert_eq!(v.kicks_clockwise[0], [(0, 0)]);
+        assert_eq!(set.t_piece(), None);
+    }
+
+    #[test]
+    fn kicks_need_a_list_per_rotation() {
+        let source = format!("[kicks]\ncw = [[[0, 0]]]\nccw = [[[0, 0]]]\n{}", V);
+        assert_eq!(
+            invalid(&source),
+            "kicks of V must have 4 lists per direction"
+        );
+    }
+
+    #[test]
+    fn t_piece_must_be_defined() {
+        let source = format!("t_piece = \"T\"\n{}", V);
+        assert_eq!(invalid(&source), "T piece T is not defined");
+    }
+
+    #[test]
+    fn every_rotation_needs_cells() {
+        let empty = V.replace(r#"["V..", "V..", "VVV"]"#, r#"["...", "..."]"#);
+        assert_eq!(invalid(&empty), "V has a rotation with no cells");
+        let none = V.replace(r#"[["V..", "V..", "VVV"]]"#, "[]");
+        assert_eq!(invalid(&none), "V has no rotations");
+    }
+
+    #[test]
+    fn spawn_rotation_must_be_a_rotation() {
+        let source = format!("{}spawn_rotation = 4\n", V);
+        assert_eq!(
+            invalid(&source),
+            "V has spawn rotation 4, which is not between 0 and 3"
+        );
+    }
+
+    #[test]
+    fn bundled_piece_sets_parse() {
+        let trominoes = PieceSet::from_toml(include_str!("../pieces/trominoes.toml")).unwrap();
+        assert_eq!(trominoes.piece_count(), 2);
+        let pentominoes = PieceSet::from_toml(include_str!("../pieces/pentominoes.toml")).unwrap();
+        assert_eq!(pentominoes.piece_count(), 18);
+        assert_eq!(pentominoes.kicks_180(PieceKind(0), 0).len(), 4);
+    }
+}
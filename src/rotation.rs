@@ -0,0 +1,245 @@
+use crate::filled::{ARS_FILLED, FILLED, I_KICKS, KICKS, NRS_FILLED, SRS_PLUS_180_KICKS};
+use crate::piece::PieceKind;
+use std::fmt::Debug;
+
+const TETROMINO_NAMES: [&str; 7] = ["O", "T", "L", "J", "S", "Z", "I"];
+
+const TETROMINO_COLORS: [(u8, u8, u8); 7] = [
+    (255, 255, 0),
+    (255, 0, 255),
+    (255, 127, 0),
+    (63, 63, 255),
+    (63, 255, 63),
+    (255, 0, 0),
+    (0, 255, 255),
+];
+
+/// The rules for which pieces exist and how they are oriented and turned.
+///
+/// Rotation states are numbered the same way in every system: 0 is the state a
+/// T piece points up in, and each step is a quarter turn clockwise from it.
+///
+/// The provided methods describe the seven standard tetrominoes, so systems
+/// that only change shapes and kicks need not override them.
+pub trait RotationSystem: Debug + Send + Sync {
+    /// Number of distinct pieces. Pieces are numbered from 0 to one less than
+    /// this.
+    fn piece_count(&self) -> usize {
+        PieceKind::TETROMINOES.len()
+    }
+    /// Short display name of `kind`.
+    fn name(&self, kind: PieceKind) -> &str {
+        TETROMINO_NAMES[kind.index()]
+    }
+    /// Display color of `kind` as an `(r, g, b)` triple.
+    fn color(&self, kind: PieceKind) -> (u8, u8, u8) {
+        TETROMINO_COLORS[kind.index()]
+    }
+    /// Cells of `kind` in the given rotation, as `(row, column)` offsets from the
+    /// bottom-left corner of its bounding box.
+    fn cells(&self, kind: PieceKind, rotation: u8) -> &[(u8, u8)];
+    /// Rotation state new pieces of `kind` enter the board in.
+    fn spawn_rotation(&self, _kind: PieceKind) -> u8 {
+        0
+    }
+    /// Offsets to try, as `(column, row)` pairs, when turning `kind` a quarter
+    /// turn from rotation `from` to rotation `to`.
+    fn kicks(&self, kind: PieceKind, from: u8, to: u8) -> &[(i8, i8)];
+    /// Offsets to try, as `(column, row)` pairs, when turning `kind` 180 degrees
+    /// out of rotation `from`. By default pieces only turn in place.
+    fn kicks_180(&self, _kind: PieceKind, _from: u8) -> &[(i8, i8)] {
+        &[(0, 0)]
+    }
+    /// The piece that can T-spin, if any.
+    fn t_piece(&self) -> Option<PieceKind> {
+        Some(PieceKind::T)
+    }
+    /// Lowest occupied row of the bounding box in the given rotation.
+    fn bottom(&self, kind: PieceKind, rotation: u8) -> i32 {
+        self.cells(kind, rotation)
+            .iter()
+            .map(|&(row, _)| row as i32)
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+fn table_cells(table: &[[(u8, u8); 28]; 4], kind: PieceKind, rotation: u8) -> &[(u8, u8)] {
+    let start = kind.index() * 4;
+    &table[rotation as usize % 4][start..start + 4]
+}
+
+/// The Super Rotation System used by guideline games, with the SRS+ kicks
+/// from Tetr.io for 180 degree turns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Srs;
+
+impl RotationSystem for Srs {
+    fn cells(&self, kind: PieceKind, rotation: u8) -> &[(u8, u8)] {
+        table_cells(&FILLED, kind, rotation)
+    }
+    fn kicks(&self, kind: PieceKind, from: u8, to: u8) -> &[(i8, i8)] {
+        let index = from as usize * 2 + (to != (from + 1) % 4) as usize;
+        match kind {
+            PieceKind::O => &[(0, 0)],
+            PieceKind::I => &I_KICKS[index],
+            _ => &KICKS[index],
+        }
+    }
+    fn kicks_180(&self, kind: PieceKind, from: u8) -> &[(i8, i8)] {
+        match kind {
+            PieceKind::O => &[(0, 0)],
+            _ => &SRS_PLUS_180_KICKS[from as usize % 4],
+        }
+    }
+}
+
+/// The Arika Rotation System from the TGM series.
+///
+/// Pieces sit at the bottom of their bounding box, T, L and J spawn flat side
+/// up, and a blocked rotation is retried one column right and then one column
+/// left. The I piece never kicks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ars;
+
+impl RotationSystem for Ars {
+    fn cells(&self, kind: PieceKind, rotation: u8) -> &[(u8, u8)] {
+        table_cells(&ARS_FILLED, kind, rotation)
+    }
+    fn spawn_rotation(&self, kind: PieceKind) -> u8 {
+        match kind {
+            PieceKind::T | PieceKind::L | PieceKind::J => 2,
+            _ => 0,
+        }
+    }
+    fn kicks(&self, kind: PieceKind, _from: u8, _to: u8) -> &[(i8, i8)] {
+        match kind {
+            PieceKind::O | PieceKind::I => &[(0, 0)],
+            _ => &[(0, 0), (1, 0), (-1, 0)],
+        }
+    }
+}
+
+/// The Nintendo Rotation System from the classic NES and Game Boy games.
+///
+/// I, S and Z only have two distinct orientations, T, L and J spawn flat side
+/// up, and there are no wall kicks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nrs;
+
+impl RotationSystem for Nrs {
+    fn cells(&self, kind: PieceKind, rotation: u8) -> &[(u8, u8)] {
+        table_cells(&NRS_FILLED, kind, rotation)
+    }
+    fn spawn_rotation(&self, kind: PieceKind) -> u8 {
+        match kind {
+            PieceKind::T | PieceKind::L | PieceKind::J => 2,
+            _ => 0,
+        }
+    }
+    fn kicks(&self, _kind: PieceKind, _from: u8, _to: u8) -> &[(i8, i8)] {
+        &[(0, 0)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::piece::{Kicks180, Piece};
+
+    #[test]
+    fn srs_kicks_match_the_guideline() {
+        assert_eq!(
+            Srs.kicks(PieceKind::T, 0, 1),
+            [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]
+        );
+        assert_eq!(
+            Srs.kicks(PieceKind::I, 0, 1),
+            [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)]
+        );
+        assert_eq!(
+            Srs.kicks(PieceKind::I, 0, 3),
+            [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)]
+        );
+        assert_eq!(Srs.kicks(PieceKind::O, 0, 1), [(0, 0)]);
+    }
+
+    #[test]
+    fn srs_kicks_undo_each_other() {
+        for &kind in &[PieceKind::T, PieceKind::I] {
+            for from in 0..4 {
+                let to = (from + 1) % 4;
+                let there = Srs.kicks(kind, from, to);
+                let back = Srs.kicks(kind, to, from);
+                let undone: Vec<_> = back.iter().map(|&(x, y)| (-x, -y)).collect();
+                assert_eq!(there, &undone[..], "{:?} {} -> {}", kind, from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn srs_uses_srs_plus_for_180_kicks() {
+        assert_eq!(
+            Srs.kicks_180(PieceKind::T, 0),
+            [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)]
+        );
+        assert_eq!(
+            Srs.kicks_180(PieceKind::I, 1),
+            [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)]
+        );
+        assert_eq!(Srs.kicks_180(PieceKind::O, 0), [(0, 0)]);
+        assert_eq!(Ars.kicks_180(PieceKind::T, 0), [(0, 0)]);
+        assert_eq!(Nrs.kicks_180(PieceKind::T, 0), [(0, 0)]);
+    }
+
+    #[test]
+    fn t_half_turns_up_off_the_floor() {
+        let board = Board::default();
+        let mut piece = Piece {
+            kind: PieceKind::T,
+            column: 3,
+            row: -1,
+            rotation: 0,
+        };
+        assert_eq!(
+            board.rotate_piece_180(&mut piece, Kicks180::System),
+            Some((0, 1))
+        );
+        assert_eq!((piece.column, piece.row, piece.rotation), (3, 0, 2));
+    }
+
+    #[test]
+    fn configured_180_kicks_override_the_system() {
+        let t = PieceKind::T;
+        assert_eq!(Kicks180::System.kicks(&Nrs, t, 0), [(0, 0)]);
+        assert_eq!(Kicks180::SrsPlus.kicks(&Nrs, t, 0), Srs.kicks_180(t, 0));
+        assert_eq!(Kicks180::None.kicks(&Srs, t, 0), [(0, 0)]);
+    }
+
+    #[test]
+    fn t_kicks_up_off_the_floor() {
+        let board = Board::default();
+        let mut piece = Piece {
+            kind: PieceKind::T,
+            column: 3,
+            row: -1,
+            rotation: 0,
+        };
+        assert_eq!(board.rotate_piece_clockwise(&mut piece), Some((-1, 1)));
+        assert_eq!((piece.column, piece.row, piece.rotation), (2, 0, 1));
+    }
+
+    #[test]
+    fn i_kicks_off_the_left_wall() {
+        let board = Board::default();
+        let mut piece = Piece {
+            kind: PieceKind::I,
+            column: -2,
+            row: 0,
+            rotation: 1,
+        };
+        assert_eq!(board.rotate_piece_clockwise(&mut piece), Some((2, 0)));
+        assert_eq!((piece.column, piece.row, piece.rotation), (0, 0, 2));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::board::{LockResult, TSpin};
+
+/// Points for each row a piece falls while soft drop is held.
+pub const SOFT_DROP_POINTS: u64 = 1;
+/// Points for each row a piece falls when hard dropped.
+pub const HARD_DROP_POINTS: u64 = 2;
+/// Points for each step of a combo after the first clear, before the level
+/// multiplier.
+pub const COMBO_POINTS: u64 = 50;
+
+/// Points the guideline awards for a lock, before the level multiplier and any
+/// back-to-back or combo bonus.
+///
+/// T-spins score whether or not they clear lines; other locks only score when
+/// they clear some.
+pub fn lock_points(result: LockResult) -> u64 {
+    match (result.t_spin, result.lines_cleared) {
+        (TSpin::None, 0) => 0,
+        (TSpin::None, 1) => 100,
+        (TSpin::None, 2) => 300,
+        (TSpin::None, 3) => 500,
+        (TSpin::None, _) => 800,
+        (TSpin::Mini, 0) => 100,
+        (TSpin::Mini, 1) => 200,
+        (TSpin::Mini, _) => 400,
+        (TSpin::Full, 0) => 400,
+        (TSpin::Full, 1) => 800,
+        (TSpin::Full, 2) => 1200,
+        (TSpin::Full, _) => 1600,
+    }
+}
+
+/// Bonus points the guideline awards for a perfect clear, before the level
+/// multiplier. A back-to-back perfect clear of four lines earns more.
+pub fn perfect_clear_points(result: LockResult) -> u64 {
+    if !result.perfect_clear {
+        return 0;
+    }
+    match result.lines_cleared {
+        1 => 800,
+        2 => 1200,
+        3 => 1800,
+        _ if result.back_to_back > 0 => 3200,
+        _ => 2000,
+    }
+}
+
+/// Points for a lock made on `level`, including the back-to-back bonus of half
+/// again and the combo and perfect clear bonuses. Levels below 1 score as level
+/// 1.
+pub fn level_points(result: LockResult, level: u32) -> u64 {
+    let mut points = lock_points(result);
+    if result.back_to_back > 0 {
+        points += points / 2;
+    }
+    points += COMBO_POINTS * result.combo.saturating_sub(1) as u64;
+    points += perfect_clear_points(result);
+    points * level.max(1) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_clears_scale_with_level() {
+        assert_eq!(level_points(LockResult::clear(1, TSpin::None), 1), 100);
+        assert_eq!(level_points(LockResult::clear(4, TSpin::None), 3), 2400);
+        assert_eq!(level_points(LockResult::clear(0, TSpin::None), 5), 0);
+        assert_eq!(level_points(LockResult::clear(2, TSpin::None), 0), 300);
+    }
+
+    #[test]
+    fn t_spins_score_without_clearing() {
+        assert_eq!(level_points(LockResult::clear(0, TSpin::Full), 1), 400);
+        assert_eq!(level_points(LockResult::clear(0, TSpin::Mini), 1), 100);
+        assert_eq!(level_points(LockResult::clear(2, TSpin::Full), 2), 2400);
+    }
+
+    #[test]
+    fn back_to_back_and_combo_add_bonuses() {
+        let b2b = LockResult {
+            back_to_back: 1,
+            ..LockResult::clear(4, TSpin::None)
+        };
+        assert_eq!(level_points(b2b, 1), 1200);
+        let combo = LockResult {
+            combo: 3,
+            ..LockResult::clear(1, TSpin::None)
+        };
+        assert_eq!(level_points(combo, 2), (100 + 2 * COMBO_POINTS) * 2);
+    }
+
+    #[test]
+    fn perfect_clears_add_their_bonus() {
+        let pc = LockResult {
+            perfect_clear: true,
+            ..LockResult::clear(4, TSpin::None)
+        };
+        assert_eq!(level_points(pc, 1), 800 + 2000);
+        let b2b_pc = LockResult {
+            back_to_back: 1,
+            ..pc
+        };
+        assert_eq!(level_points(b2b_pc, 1), 1200 + 3200);
+    }
+}
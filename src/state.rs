@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+/// The phase of a game's lifecycle.
+///
+/// Countdown states carry the number of ticks left before they advance on their
+/// own; the remaining states only change in response to input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    /// Waiting on the title screen. Any action other than pause starts the game.
+    Title,
+    /// Counting down before play begins.
+    Ready(u32),
+    /// Pieces are falling and the clock is running.
+    Playing,
+    /// Play is suspended until pause is pressed again.
+    Paused,
+    /// The line goal was reached; the results follow shortly.
+    Finished(u32),
+    /// The stack reached the top of the board; the results follow shortly.
+    ToppedOut(u32),
+    /// The run is over. Restart begins a new countdown.
+    Results,
+}
+
+impl GameState {
+    /// Whether the run has ended, successfully or not.
+    pub fn is_over(self) -> bool {
+        matches!(
+            self,
+            GameState::Finished(_) | GameState::ToppedOut(_) | GameState::Results
+        )
+    }
+}
+
+/// How a game was lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopOut {
+    /// A new piece spawned overlapping the stack.
+    BlockOut,
+    /// A piece locked entirely above the visible field.
+    LockOut,
+}
+
+/// Final statistics of a run that has ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameResults {
+    /// In-game time the run took.
+    pub time: Duration,
+    /// Total lines cleared.
+    pub lines_cleared: u32,
+    /// Level the run ended on.
+    pub level: u32,
+    /// Final score.
+    pub score: u64,
+    /// Number of perfect clears made.
+    pub perfect_clears: u32,
+    /// Total garbage lines sent.
+    pub attack: u32,
+    /// How the run was lost, or None if the line goal was reached.
+    pub top_out: Option<TopOut>,
+}